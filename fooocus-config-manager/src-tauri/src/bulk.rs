@@ -0,0 +1,158 @@
+use crate::database::{LoRA, PresetConfig};
+use crate::diff::{self, PresetChange};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+/// Sections whose fields a bulk update may set or scale. Names, parents and
+/// other per-preset fields are left to the editor.
+const BULK_SECTIONS: &[&str] = &["sampling", "model", "image", "prompt"];
+
+/// One change applied to every preset of a bulk update.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "op", rename_all = "camelCase", rename_all_fields = "camelCase")]
+pub enum PresetEdit {
+    /// Sets a field such as `sampling.scheduler` to a value.
+    SetField { path: String, value: Value },
+    /// Multiplies a numeric field such as `sampling.cfgScale`. Integer fields
+    /// are rounded.
+    ScaleField { path: String, factor: f64 },
+    AddTag { tag: String },
+    RemoveTag { tag: String },
+    /// Points LoRAs using model file `from` at `to`, optionally with a new weight.
+    ReplaceLora {
+        from: String,
+        to: String,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        weight: Option<f64>,
+    },
+    RemoveLora { model_name: String },
+    AddLora { lora: LoRA },
+    AppendPrompt { negative: bool, text: String },
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BulkUpdateSummary {
+    pub preset_id: String,
+    pub preset_name: String,
+    /// Empty when the edits left the preset as it was.
+    pub changes: Vec<PresetChange>,
+}
+
+/// Runs a field edit on the JSON form of the preset. Only fields inside
+/// `BULK_SECTIONS` can be edited.
+fn edit_field(preset: &PresetConfig, path: &str, edit: impl FnOnce(&mut Value) -> Result<(), String>) -> Result<PresetConfig, String> {
+    match path.split_once('.') {
+        Some((section, _)) if BULK_SECTIONS.contains(&section) => {}
+        _ => return Err(format!("Cannot bulk edit {}: only sampling, model, image and prompt fields can be edited", path)),
+    }
+    let pointer = format!("/{}", path.replace('.', "/"));
+    if diff::settings_value(preset)?.pointer(&pointer).is_none() {
+        return Err(format!("Unknown preset field: {}", path));
+    }
+
+    let mut value = serde_json::to_value(preset).map_err(|e| e.to_string())?;
+    if let Some(field) = value.pointer_mut(&pointer) {
+        edit(field)?;
+    }
+    serde_json::from_value(value).map_err(|e| format!("Invalid value for {}: {}", path, e))
+}
+
+fn same_file(a: &str, b: &str) -> bool {
+    a.eq_ignore_ascii_case(b)
+}
+
+/// Clears the model id that belongs to a model name field such as
+/// `model.baseModel` or `model.loras.0.modelName` when the name changed, so
+/// relinking looks the new name up instead of keeping the old model.
+fn clear_stale_model_id(before: &PresetConfig, after: &mut PresetConfig, path: &str) {
+    let (old, new) = (&before.model, &mut after.model);
+    match path {
+        "model.baseModel" if old.base_model != new.base_model => new.base_model_id = None,
+        "model.refinerModel" if old.refiner_model != new.refiner_model => new.refiner_model_id = None,
+        "model.vae" if old.vae != new.vae => new.vae_model_id = None,
+        _ => {
+            let index = path.strip_prefix("model.loras.")
+                .and_then(|rest| rest.strip_suffix(".modelName"))
+                .and_then(|index| index.parse::<usize>().ok());
+            if let Some(index) = index {
+                if let (Some(old), Some(new)) = (old.loras.get(index), new.loras.get_mut(index)) {
+                    if old.model_name != new.model_name {
+                        new.model_id = None;
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Applies edits in order. Model ids of references whose model name is set,
+/// and of replaced or added LoRAs, are cleared, so the caller should relink
+/// the result.
+pub fn apply_edits(preset: &PresetConfig, edits: &[PresetEdit]) -> Result<PresetConfig, String> {
+    let mut preset = preset.clone();
+    for edit in edits {
+        match edit {
+            PresetEdit::SetField { path, value } => {
+                let mut edited = edit_field(&preset, path, |field| {
+                    *field = value.clone();
+                    Ok(())
+                })?;
+                clear_stale_model_id(&preset, &mut edited, path);
+                preset = edited;
+            }
+            PresetEdit::ScaleField { path, factor } => {
+                preset = edit_field(&preset, path, |field| {
+                    *field = match (field.as_i64(), field.as_f64()) {
+                        (Some(n), _) => Value::from((n as f64 * factor).round() as i64),
+                        (None, Some(n)) => serde_json::Number::from_f64(n * factor)
+                            .map(Value::Number)
+                            .ok_or_else(|| format!("Cannot scale {} by {}", path, factor))?,
+                        _ => return Err(format!("Not a numeric field: {}", path)),
+                    };
+                    Ok(())
+                })?;
+            }
+            PresetEdit::AddTag { tag } => {
+                if !preset.tags.contains(tag) {
+                    preset.tags.push(tag.clone());
+                }
+            }
+            PresetEdit::RemoveTag { tag } => preset.tags.retain(|t| t != tag),
+            PresetEdit::ReplaceLora { from, to, weight } => {
+                for lora in preset.model.loras.iter_mut().filter(|l| same_file(&l.model_name, from)) {
+                    if lora.name == lora.model_name {
+                        lora.name = to.clone();
+                    }
+                    lora.model_name = to.clone();
+                    lora.model_id = None;
+                    if let Some(weight) = weight {
+                        lora.weight = *weight;
+                    }
+                }
+            }
+            PresetEdit::RemoveLora { model_name } => {
+                preset.model.loras.retain(|l| !same_file(&l.model_name, model_name));
+            }
+            PresetEdit::AddLora { lora } => {
+                if !preset.model.loras.iter().any(|l| same_file(&l.model_name, &lora.model_name)) {
+                    preset.model.loras.push(LoRA {
+                        model_id: None,
+                        ..lora.clone()
+                    });
+                }
+            }
+            PresetEdit::AppendPrompt { negative, text } => {
+                let prompt = if *negative { &mut preset.prompt.negative } else { &mut preset.prompt.positive };
+                let text = text.trim();
+                if !text.is_empty() && !prompt.contains(text) {
+                    if !prompt.trim().is_empty() {
+                        prompt.push_str(", ");
+                    }
+                    prompt.push_str(text);
+                }
+            }
+        }
+    }
+    Ok(preset)
+}
@@ -0,0 +1,309 @@
+use crate::database::{PresetConfig, PRESET_COLUMNS};
+use rusqlite::{params, Connection, OptionalExtension, Result as SqliteResult};
+use serde::{Deserialize, Serialize};
+
+/// A folder of presets. Collections nest through `parent_id` and are ordered
+/// among their siblings by `position`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Collection {
+    pub id: String,
+    pub name: String,
+    pub description: String,
+    pub parent_id: Option<String>,
+    #[serde(default)]
+    pub position: i64,
+    /// Member presets in their manual order.
+    #[serde(default)]
+    pub preset_ids: Vec<String>,
+    pub created_at: String,
+    pub updated_at: String,
+}
+
+/// A collection with its presets and, recursively, its sub-collections.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CollectionExport {
+    pub name: String,
+    pub description: String,
+    pub presets: Vec<PresetConfig>,
+    pub children: Vec<CollectionExport>,
+}
+
+/// Live member presets in order. Trashed presets keep their membership so
+/// they come back in place when restored.
+fn load_preset_ids(conn: &Connection, collection_id: &str) -> SqliteResult<Vec<String>> {
+    let mut stmt = conn.prepare(
+        "SELECT c.preset_id FROM collection_presets c JOIN presets p ON p.id = c.preset_id
+         WHERE c.collection_id = ?1 AND p.deleted_at IS NULL ORDER BY c.position"
+    )?;
+    let ids = stmt.query_map(params![collection_id], |row| row.get(0))?;
+    ids.collect()
+}
+
+fn collection_from_row(conn: &Connection, row: (String, String, Option<String>, Option<String>, i64, String, String)) -> SqliteResult<Collection> {
+    let (id, name, description, parent_id, position, created_at, updated_at) = row;
+    Ok(Collection {
+        preset_ids: load_preset_ids(conn, &id)?,
+        id,
+        name,
+        description: description.unwrap_or_default(),
+        parent_id,
+        position,
+        created_at,
+        updated_at,
+    })
+}
+
+const COLLECTION_COLUMNS: &str = "id, name, description, parent_id, position, created_at, updated_at";
+
+pub fn load_collections(conn: &Connection) -> SqliteResult<Vec<Collection>> {
+    let rows: Vec<_> = {
+        let mut stmt = conn.prepare(&format!(
+            "SELECT {} FROM collections ORDER BY parent_id, position",
+            COLLECTION_COLUMNS
+        ))?;
+        let rows = stmt.query_map([], |row| {
+            Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?, row.get(4)?, row.get(5)?, row.get(6)?))
+        })?;
+        rows.collect::<SqliteResult<_>>()?
+    };
+    rows.into_iter().map(|row| collection_from_row(conn, row)).collect()
+}
+
+pub fn load_collection(conn: &Connection, id: &str) -> SqliteResult<Option<Collection>> {
+    let row = conn.query_row(
+        &format!("SELECT {} FROM collections WHERE id = ?1", COLLECTION_COLUMNS),
+        params![id],
+        |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?, row.get(4)?, row.get(5)?, row.get(6)?)),
+    ).optional()?;
+    row.map(|row| collection_from_row(conn, row)).transpose()
+}
+
+pub fn require_collection(conn: &Connection, id: &str) -> Result<Collection, String> {
+    load_collection(conn, id).map_err(|e| e.to_string())?
+        .ok_or_else(|| format!("Collection not found: {}", id))
+}
+
+fn sibling_ids(conn: &Connection, parent_id: Option<&str>) -> SqliteResult<Vec<String>> {
+    let mut stmt = conn.prepare("SELECT id FROM collections WHERE parent_id IS ?1 ORDER BY position")?;
+    let ids = stmt.query_map(params![parent_id], |row| row.get(0))?;
+    ids.collect()
+}
+
+fn write_positions(conn: &Connection, ids: &[String]) -> SqliteResult<()> {
+    for (position, id) in ids.iter().enumerate() {
+        conn.execute("UPDATE collections SET position = ?1 WHERE id = ?2", params![position as i64, id])?;
+    }
+    Ok(())
+}
+
+/// Checks that `parent_id` exists and is neither `id` nor one of its
+/// descendants.
+fn check_parent(conn: &Connection, id: &str, parent_id: &str) -> Result<(), String> {
+    require_collection(conn, parent_id)?;
+
+    let mut ancestor = Some(parent_id.to_string());
+    while let Some(current) = ancestor {
+        if current == id {
+            return Err("A collection cannot be moved into itself or one of its sub-collections".to_string());
+        }
+        ancestor = conn.query_row(
+            "SELECT parent_id FROM collections WHERE id = ?1",
+            params![current],
+            |row| row.get(0),
+        ).optional().map_err(|e| e.to_string())?.flatten();
+    }
+    Ok(())
+}
+
+/// Inserts a new collection at the end of its parent's sub-collections.
+pub fn insert_collection(conn: &Connection, collection: &mut Collection) -> Result<(), String> {
+    if collection.name.trim().is_empty() {
+        return Err("Collection name cannot be empty".to_string());
+    }
+    if let Some(parent_id) = &collection.parent_id {
+        check_parent(conn, &collection.id, parent_id)?;
+    }
+
+    collection.position = sibling_ids(conn, collection.parent_id.as_deref()).map_err(|e| e.to_string())?.len() as i64;
+    collection.preset_ids.clear();
+    conn.execute(
+        "INSERT INTO collections (id, name, description, parent_id, position, created_at, updated_at)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+        params![
+            collection.id,
+            collection.name,
+            collection.description,
+            collection.parent_id,
+            collection.position,
+            collection.created_at,
+            collection.updated_at,
+        ],
+    ).map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+/// Saves a collection's name and description. Placement and members change
+/// through their own functions.
+pub fn update_collection(conn: &Connection, collection: &Collection) -> Result<(), String> {
+    if collection.name.trim().is_empty() {
+        return Err("Collection name cannot be empty".to_string());
+    }
+    let updated = conn.execute(
+        "UPDATE collections SET name = ?1, description = ?2, updated_at = ?3 WHERE id = ?4",
+        params![collection.name, collection.description, collection.updated_at, collection.id],
+    ).map_err(|e| e.to_string())?;
+    if updated == 0 {
+        return Err(format!("Collection not found: {}", collection.id));
+    }
+    Ok(())
+}
+
+/// Moves a collection under `parent_id` (the top level without one) at
+/// `position` among its new siblings, or last. Also used to reorder
+/// collections within the same parent.
+pub fn move_collection(conn: &Connection, id: &str, parent_id: Option<&str>, position: Option<usize>, now: &str) -> Result<(), String> {
+    let collection = require_collection(conn, id)?;
+    if let Some(parent_id) = parent_id {
+        check_parent(conn, id, parent_id)?;
+    }
+
+    let mut old_siblings = sibling_ids(conn, collection.parent_id.as_deref()).map_err(|e| e.to_string())?;
+    old_siblings.retain(|s| s != id);
+    write_positions(conn, &old_siblings).map_err(|e| e.to_string())?;
+
+    let mut siblings = sibling_ids(conn, parent_id).map_err(|e| e.to_string())?;
+    siblings.retain(|s| s != id);
+    let position = position.unwrap_or(siblings.len()).min(siblings.len());
+    siblings.insert(position, id.to_string());
+
+    conn.execute(
+        "UPDATE collections SET parent_id = ?1, updated_at = ?2 WHERE id = ?3",
+        params![parent_id, now, id],
+    ).map_err(|e| e.to_string())?;
+    write_positions(conn, &siblings).map_err(|e| e.to_string())
+}
+
+/// Deletes a collection. Its sub-collections move up to its parent and its
+/// presets stay in the library.
+pub fn delete_collection(conn: &Connection, id: &str, now: &str) -> Result<(), String> {
+    let collection = require_collection(conn, id)?;
+    for child in sibling_ids(conn, Some(id)).map_err(|e| e.to_string())? {
+        move_collection(conn, &child, collection.parent_id.as_deref(), None, now)?;
+    }
+
+    conn.execute("DELETE FROM collection_presets WHERE collection_id = ?1", params![id]).map_err(|e| e.to_string())?;
+    conn.execute("DELETE FROM collections WHERE id = ?1", params![id]).map_err(|e| e.to_string())?;
+    let mut siblings = sibling_ids(conn, collection.parent_id.as_deref()).map_err(|e| e.to_string())?;
+    siblings.retain(|s| s != id);
+    write_positions(conn, &siblings).map_err(|e| e.to_string())
+}
+
+/// Every member including trashed presets, in order.
+fn all_member_ids(conn: &Connection, collection_id: &str) -> SqliteResult<Vec<String>> {
+    let mut stmt = conn.prepare("SELECT preset_id FROM collection_presets WHERE collection_id = ?1 ORDER BY position")?;
+    let ids = stmt.query_map(params![collection_id], |row| row.get(0))?;
+    ids.collect()
+}
+
+fn write_members(conn: &Connection, collection_id: &str, preset_ids: &[String], now: &str) -> SqliteResult<()> {
+    conn.execute("DELETE FROM collection_presets WHERE collection_id = ?1", params![collection_id])?;
+    for (position, preset_id) in preset_ids.iter().enumerate() {
+        conn.execute(
+            "INSERT INTO collection_presets (collection_id, preset_id, position) VALUES (?1, ?2, ?3)",
+            params![collection_id, preset_id, position as i64],
+        )?;
+    }
+    conn.execute("UPDATE collections SET updated_at = ?1 WHERE id = ?2", params![now, collection_id])?;
+    Ok(())
+}
+
+/// Adds presets to a collection at `position` among its live presets, or at
+/// the end. Presets that are already members are moved there instead.
+pub fn add_presets(conn: &Connection, collection_id: &str, preset_ids: &[String], position: Option<usize>, now: &str) -> Result<(), String> {
+    require_collection(conn, collection_id)?;
+    let mut unique: Vec<String> = Vec::new();
+    for preset_id in preset_ids {
+        if !unique.contains(preset_id) {
+            unique.push(preset_id.clone());
+        }
+    }
+    let preset_ids = unique.as_slice();
+    for preset_id in preset_ids {
+        let exists: bool = conn.query_row(
+            "SELECT EXISTS(SELECT 1 FROM presets WHERE id = ?1 AND deleted_at IS NULL)",
+            params![preset_id],
+            |row| row.get(0),
+        ).map_err(|e| e.to_string())?;
+        if !exists {
+            return Err(format!("Preset not found: {}", preset_id));
+        }
+    }
+
+    let mut members = all_member_ids(conn, collection_id).map_err(|e| e.to_string())?;
+    members.retain(|id| !preset_ids.contains(id));
+    let mut live = load_preset_ids(conn, collection_id).map_err(|e| e.to_string())?;
+    live.retain(|id| !preset_ids.contains(id));
+    // Insert before the live preset shown at `position`, so trashed members
+    // in between do not shift the drop point.
+    let index = position
+        .and_then(|position| live.get(position))
+        .and_then(|next| members.iter().position(|id| id == next))
+        .unwrap_or(members.len());
+    for (offset, preset_id) in preset_ids.iter().enumerate() {
+        members.insert(index + offset, preset_id.clone());
+    }
+    write_members(conn, collection_id, &members, now).map_err(|e| e.to_string())
+}
+
+pub fn remove_presets(conn: &Connection, collection_id: &str, preset_ids: &[String], now: &str) -> Result<(), String> {
+    require_collection(conn, collection_id)?;
+    let mut members = all_member_ids(conn, collection_id).map_err(|e| e.to_string())?;
+    members.retain(|id| !preset_ids.contains(id));
+    write_members(conn, collection_id, &members, now).map_err(|e| e.to_string())
+}
+
+/// Sets the manual order of a collection's presets. `preset_ids` must list
+/// every live member exactly once.
+pub fn reorder_presets(conn: &Connection, collection_id: &str, preset_ids: &[String], now: &str) -> Result<(), String> {
+    let collection = require_collection(conn, collection_id)?;
+    let mut expected = collection.preset_ids.clone();
+    let mut given = preset_ids.to_vec();
+    expected.sort();
+    given.sort();
+    if expected != given {
+        return Err(format!("The new order must list each preset of {} exactly once", collection.name));
+    }
+
+    let mut members = preset_ids.to_vec();
+    members.extend(all_member_ids(conn, collection_id).map_err(|e| e.to_string())?
+        .into_iter()
+        .filter(|id| !preset_ids.contains(id)));
+    write_members(conn, collection_id, &members, now).map_err(|e| e.to_string())
+}
+
+/// Collects a collection's presets and sub-collections for export.
+pub fn export_collection(conn: &Connection, id: &str) -> Result<CollectionExport, String> {
+    let collection = require_collection(conn, id)?;
+    let mut presets = Vec::new();
+    for preset_id in &collection.preset_ids {
+        presets.push(conn.query_row(
+            &format!("SELECT {} FROM presets WHERE id = ?1", PRESET_COLUMNS),
+            params![preset_id],
+            PresetConfig::from_row,
+        ).map_err(|e| e.to_string())?);
+    }
+
+    let children = sibling_ids(conn, Some(id)).map_err(|e| e.to_string())?
+        .iter()
+        .map(|child| export_collection(conn, child))
+        .collect::<Result<_, _>>()?;
+
+    Ok(CollectionExport {
+        name: collection.name,
+        description: collection.description,
+        presets,
+        children,
+    })
+}
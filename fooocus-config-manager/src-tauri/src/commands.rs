@@ -1,6 +1,29 @@
-use crate::database::{Database, PresetConfig, Tag, ModelConfig, SamplingConfig, PromptConfig, ImageConfig, ModelInfo, ModelUsageInfo};
-use tauri::State;
-use rusqlite::params;
+use crate::database::{Database, ModelType, PresetConfig, PresetSummary, Tag, ModelInfo, ModelUsageInfo, ModelUpdateResult, ModelDeletionResult, PRESET_COLUMNS, MODEL_COLUMNS, insert_model, insert_preset_row, update_model_row, update_preset_row, load_all_models, load_all_presets};
+use crate::bulk::{self, BulkUpdateSummary, PresetEdit};
+use crate::collections::{self, Collection, CollectionExport};
+use crate::database::{ModelMergeResult, ResourceDownloads, get_setting, set_setting};
+use crate::downloads::{self, DownloadReport, FooocusPaths};
+use crate::diff::{self, PresetChange};
+use crate::embeddings;
+use crate::families::{self, ModelFamily, PresetUpgrade};
+use crate::gallery::{self, PresetImage};
+use crate::inheritance;
+use crate::linking::{self, DeleteStrategy, ModelCandidate, ModelDeletionUndo, ModelSlot, PresetReferenceChanges, RelinkReport};
+use crate::lint::{self, LintWarning};
+use crate::merge::{self, MergeSide, PresetMerge};
+use crate::metadata::{self, SidecarImportPreview};
+use crate::outcomes::{self, Outcome, OutcomeSummary, PresetOutcome, PresetRanking};
+use crate::model_files::{self, DuplicateGroup, DuplicateModelEntry, DuplicateReport};
+use crate::resources::{self, ResourceIssue};
+use crate::revisions::{self, PresetRevision};
+use crate::stats::{self, LibraryStats};
+use crate::sweep::{self, PresetSweep, SweepAxis, SweepTarget};
+use crate::thumbnails;
+use crate::trash::{self, TrashItem, TrashKind};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use tauri::{AppHandle, Emitter, Manager, State};
+use rusqlite::{params, Connection, OptionalExtension};
 use serde_json;
 use uuid::Uuid;
 use chrono::Utc;
@@ -8,101 +31,18 @@ use chrono::Utc;
 #[tauri::command]
 pub fn get_all_presets(db: State<'_, Database>) -> Result<Vec<PresetConfig>, String> {
     let conn = db.0.lock().map_err(|e| e.to_string())?;
-    let mut stmt = conn.prepare(
-        "SELECT id, name, description, tags, is_favorite, use_count, created_at, updated_at, 
-                model_config, sampling_config, prompt_config, image_config, resources 
-         FROM presets ORDER BY updated_at DESC"
-    ).map_err(|e| e.to_string())?;
-
-    let presets = stmt.query_map([], |row| {
-        Ok(PresetConfig {
-            id: row.get(0)?,
-            name: row.get(1)?,
-            description: row.get(2)?,
-            tags: serde_json::from_str(&row.get::<_, String>(3)?).unwrap_or_default(),
-            is_favorite: row.get::<_, i32>(4)? != 0,
-            use_count: row.get(5)?,
-            created_at: row.get(6)?,
-            updated_at: row.get(7)?,
-            model: serde_json::from_str(&row.get::<_, String>(8)?).unwrap_or_else(|_| ModelConfig {
-                base_model: String::new(),
-                base_model_id: None,
-                refiner_model: String::new(),
-                refiner_model_id: None,
-                refiner_switch: 0.5,
-                loras: vec![],
-            }),
-            sampling: serde_json::from_str(&row.get::<_, String>(9)?).unwrap_or_else(|_| SamplingConfig {
-                cfg_scale: 7.0,
-                sample_sharpness: 2.0,
-                sampler: String::from("dpmpp_2m_sde_gpu"),
-                scheduler: String::from("karras"),
-                performance: String::from("Speed"),
-                steps: 30,
-            }),
-            prompt: serde_json::from_str(&row.get::<_, String>(10)?).unwrap_or_else(|_| PromptConfig {
-                positive: String::new(),
-                negative: String::new(),
-                styles: vec![],
-            }),
-            image: serde_json::from_str(&row.get::<_, String>(11)?).unwrap_or_else(|_| ImageConfig {
-                aspect_ratio: String::from("1152*896"),
-                image_count: 4,
-            }),
-            resources: row.get::<_, Option<String>>(12)?.and_then(|s| serde_json::from_str(&s).ok()),
-        })
-    }).map_err(|e| e.to_string())?;
-
-    presets.collect::<Result<Vec<_>, _>>().map_err(|e| e.to_string())
+    load_all_presets(&conn).map_err(|e| e.to_string())
 }
 
 #[tauri::command]
 pub fn get_preset_by_id(db: State<'_, Database>, id: String) -> Result<Option<PresetConfig>, String> {
     let conn = db.0.lock().map_err(|e| e.to_string())?;
-    let mut stmt = conn.prepare(
-        "SELECT id, name, description, tags, is_favorite, use_count, created_at, updated_at, 
-                model_config, sampling_config, prompt_config, image_config, resources 
-         FROM presets WHERE id = ?1"
-    ).map_err(|e| e.to_string())?;
+    let mut stmt = conn.prepare(&format!(
+        "SELECT {} FROM presets WHERE id = ?1 AND deleted_at IS NULL",
+        PRESET_COLUMNS
+    )).map_err(|e| e.to_string())?;
 
-    let result = stmt.query_row(params![id], |row| {
-        Ok(PresetConfig {
-            id: row.get(0)?,
-            name: row.get(1)?,
-            description: row.get(2)?,
-            tags: serde_json::from_str(&row.get::<_, String>(3)?).unwrap_or_default(),
-            is_favorite: row.get::<_, i32>(4)? != 0,
-            use_count: row.get(5)?,
-            created_at: row.get(6)?,
-            updated_at: row.get(7)?,
-            model: serde_json::from_str(&row.get::<_, String>(8)?).unwrap_or_else(|_| ModelConfig {
-                base_model: String::new(),
-                base_model_id: None,
-                refiner_model: String::new(),
-                refiner_model_id: None,
-                refiner_switch: 0.5,
-                loras: vec![],
-            }),
-            sampling: serde_json::from_str(&row.get::<_, String>(9)?).unwrap_or_else(|_| SamplingConfig {
-                cfg_scale: 7.0,
-                sample_sharpness: 2.0,
-                sampler: String::from("dpmpp_2m_sde_gpu"),
-                scheduler: String::from("karras"),
-                performance: String::from("Speed"),
-                steps: 30,
-            }),
-            prompt: serde_json::from_str(&row.get::<_, String>(10)?).unwrap_or_else(|_| PromptConfig {
-                positive: String::new(),
-                negative: String::new(),
-                styles: vec![],
-            }),
-            image: serde_json::from_str(&row.get::<_, String>(11)?).unwrap_or_else(|_| ImageConfig {
-                aspect_ratio: String::from("1152*896"),
-                image_count: 4,
-            }),
-            resources: row.get::<_, Option<String>>(12)?.and_then(|s| serde_json::from_str(&s).ok()),
-        })
-    });
+    let result = stmt.query_row(params![id], PresetConfig::from_row);
 
     match result {
         Ok(preset) => Ok(Some(preset)),
@@ -116,96 +56,139 @@ pub fn create_preset(db: State<'_, Database>, preset: PresetConfig) -> Result<Pr
     let conn = db.0.lock().map_err(|e| e.to_string())?;
     let id = Uuid::new_v4().to_string();
     let now = Utc::now().to_rfc3339();
-    
-    let model_json = serde_json::to_string(&preset.model).map_err(|e| e.to_string())?;
-    let sampling_json = serde_json::to_string(&preset.sampling).map_err(|e| e.to_string())?;
-    let prompt_json = serde_json::to_string(&preset.prompt).map_err(|e| e.to_string())?;
-    let image_json = serde_json::to_string(&preset.image).map_err(|e| e.to_string())?;
-    let tags_json = serde_json::to_string(&preset.tags).map_err(|e| e.to_string())?;
-    let resources_json = preset.resources.as_ref()
-        .map(|r| serde_json::to_string(r).unwrap_or_default());
 
-    conn.execute(
-        "INSERT INTO presets (id, name, description, tags, is_favorite, use_count, created_at, updated_at, 
-         model_config, sampling_config, prompt_config, image_config, resources)
-         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13)",
-        params![
-            id,
-            preset.name,
-            preset.description,
-            tags_json,
-            preset.is_favorite as i32,
-            0,
-            now,
-            now,
-            model_json,
-            sampling_json,
-            prompt_json,
-            image_json,
-            resources_json,
-        ],
-    ).map_err(|e| e.to_string())?;
+    resources::validate_resources(preset.resources.as_ref())?;
+    outcomes::validate_rating(preset.rating)?;
 
-    Ok(PresetConfig {
+    let mut preset = PresetConfig {
         id,
-        name: preset.name,
-        description: preset.description,
-        tags: preset.tags,
-        is_favorite: preset.is_favorite,
         use_count: 0,
         created_at: now.clone(),
-        updated_at: now,
-        model: preset.model,
-        sampling: preset.sampling,
-        prompt: preset.prompt,
-        image: preset.image,
-        resources: preset.resources,
-    })
+        updated_at: now.clone(),
+        ..preset
+    };
+    inheritance::prepare_child(&conn, &mut preset)?;
+
+    let models = load_all_models(&conn).map_err(|e| e.to_string())?;
+    linking::link_preset(&mut preset, &models, &mut RelinkReport::default());
+
+    insert_preset_row(&conn, &preset).map_err(|e| e.to_string())?;
+    revisions::record_revision(&conn, &preset, &now, None).map_err(|e| e.to_string())?;
+
+    Ok(preset)
 }
 
+/// Saves a preset. Fields a child preset shares with its parent become
+/// inherited, and presets inheriting from this one are updated to match.
 #[tauri::command]
 pub fn update_preset(db: State<'_, Database>, preset: PresetConfig) -> Result<PresetConfig, String> {
-    let conn = db.0.lock().map_err(|e| e.to_string())?;
+    resources::validate_resources(preset.resources.as_ref())?;
+    outcomes::validate_rating(preset.rating)?;
+
+    let mut conn = db.0.lock().map_err(|e| e.to_string())?;
     let now = Utc::now().to_rfc3339();
-    
-    let model_json = serde_json::to_string(&preset.model).map_err(|e| e.to_string())?;
-    let sampling_json = serde_json::to_string(&preset.sampling).map_err(|e| e.to_string())?;
-    let prompt_json = serde_json::to_string(&preset.prompt).map_err(|e| e.to_string())?;
-    let image_json = serde_json::to_string(&preset.image).map_err(|e| e.to_string())?;
-    let tags_json = serde_json::to_string(&preset.tags).map_err(|e| e.to_string())?;
-    let resources_json = preset.resources.as_ref()
-        .map(|r| serde_json::to_string(r).unwrap_or_default());
+    let mut preset = PresetConfig {
+        updated_at: now.clone(),
+        ..preset
+    };
 
-    conn.execute(
-        "UPDATE presets SET name = ?1, description = ?2, tags = ?3, is_favorite = ?4, 
-         updated_at = ?5, model_config = ?6, sampling_config = ?7, prompt_config = ?8, 
-         image_config = ?9, resources = ?10 WHERE id = ?11",
-        params![
-            preset.name,
-            preset.description,
-            tags_json,
-            preset.is_favorite as i32,
-            now,
-            model_json,
-            sampling_json,
-            prompt_json,
-            image_json,
-            resources_json,
-            preset.id,
-        ],
-    ).map_err(|e| e.to_string())?;
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+    inheritance::prepare_child(&tx, &mut preset)?;
+    revisions::save_preset(&tx, &preset, &now).map_err(|e| e.to_string())?;
+    inheritance::refresh_children(&tx, &preset, &now)?;
+    tx.commit().map_err(|e| e.to_string())?;
 
-    Ok(PresetConfig {
-        updated_at: now,
-        ..preset
-    })
+    Ok(preset)
+}
+
+/// Moves a preset to the trash; `purge_trash` deletes it for good. Presets
+/// inheriting from it keep their settings as their own and are returned.
+#[tauri::command]
+pub fn delete_preset(db: State<'_, Database>, id: String) -> Result<Vec<PresetConfig>, String> {
+    let mut conn = db.0.lock().map_err(|e| e.to_string())?;
+    let now = Utc::now().to_rfc3339();
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+    if !trash::move_to_trash(&tx, TrashKind::Preset, &id, &now).map_err(|e| e.to_string())? {
+        return Err(format!("Preset not found: {}", id));
+    }
+    let detached = inheritance::detach_children(&tx, &id, &now)?;
+    tx.commit().map_err(|e| e.to_string())?;
+    Ok(detached)
+}
+
+/// Copies a preset and its gallery under a new name, recording the source so
+/// forks can be listed. The copy starts unused.
+#[tauri::command]
+pub fn duplicate_preset(app: AppHandle, db: State<'_, Database>, id: String, new_name: String) -> Result<PresetConfig, String> {
+    let new_name = new_name.trim();
+    if new_name.is_empty() {
+        return Err("Preset name cannot be empty".to_string());
+    }
+
+    let mut conn = db.0.lock().map_err(|e| e.to_string())?;
+    let now = Utc::now().to_rfc3339();
+    let source = load_live_preset(&conn, &id)?;
+    let images = gallery::load_preset_images(&conn, &id).map_err(|e| e.to_string())?;
+
+    let preset = PresetConfig {
+        id: Uuid::new_v4().to_string(),
+        name: new_name.to_string(),
+        use_count: 0,
+        rating: None,
+        created_at: now.clone(),
+        updated_at: now.clone(),
+        source_preset_id: Some(source.id.clone()),
+        ..source
+    };
+    let (gallery_dir, _) = gallery_dirs(&app, &preset.id)?;
+
+    if let Err(e) = insert_duplicate(&mut conn, &preset, images, &gallery_dir, &now) {
+        // The folder is new, so it only holds copies made for this duplicate.
+        std::fs::remove_dir_all(&gallery_dir).ok();
+        return Err(e);
+    }
+
+    Ok(preset)
+}
+
+fn insert_duplicate(conn: &mut Connection, preset: &PresetConfig, images: Vec<PresetImage>, gallery_dir: &Path, now: &str) -> Result<(), String> {
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+    insert_preset_row(&tx, preset).map_err(|e| e.to_string())?;
+    for image in images {
+        let image_id = Uuid::new_v4().to_string();
+        // Files the app owns are copied again, since each gallery deletes its own.
+        let path = if image.is_copy {
+            let source = PathBuf::from(&image.path);
+            if !source.is_file() {
+                continue;
+            }
+            thumbnails::store_image(&source, gallery_dir, &image_id)?
+        } else {
+            PathBuf::from(&image.path)
+        };
+        gallery::insert_preset_image(&tx, &PresetImage {
+            id: image_id,
+            preset_id: preset.id.clone(),
+            path: path.to_string_lossy().to_string(),
+            created_at: now.to_string(),
+            ..image
+        }).map_err(|e| e.to_string())?;
+    }
+    revisions::record_revision(&tx, preset, now, None).map_err(|e| e.to_string())?;
+    tx.commit().map_err(|e| e.to_string())
 }
 
+/// Presets duplicated from `id`.
 #[tauri::command]
-pub fn delete_preset(db: State<'_, Database>, id: String) -> Result<(), String> {
+pub fn get_preset_forks(db: State<'_, Database>, id: String) -> Result<Vec<PresetSummary>, String> {
     let conn = db.0.lock().map_err(|e| e.to_string())?;
-    conn.execute("DELETE FROM presets WHERE id = ?1", params![id]).map_err(|e| e.to_string())?;
-    Ok(())
+    let mut stmt = conn.prepare(
+        "SELECT id, name FROM presets WHERE source_preset_id = ?1 AND deleted_at IS NULL ORDER BY created_at"
+    ).map_err(|e| e.to_string())?;
+    let forks = stmt.query_map(params![id], |row| {
+        Ok(PresetSummary { id: row.get(0)?, name: row.get(1)? })
+    }).map_err(|e| e.to_string())?;
+    forks.collect::<Result<Vec<_>, _>>().map_err(|e| e.to_string())
 }
 
 #[tauri::command]
@@ -213,51 +196,13 @@ pub fn search_presets(db: State<'_, Database>, query: String) -> Result<Vec<Pres
     let conn = db.0.lock().map_err(|e| e.to_string())?;
     let search_pattern = format!("%{}%", query);
     
-    let mut stmt = conn.prepare(
-        "SELECT id, name, description, tags, is_favorite, use_count, created_at, updated_at, 
-                model_config, sampling_config, prompt_config, image_config, resources 
-         FROM presets WHERE name LIKE ?1 OR description LIKE ?1 OR tags LIKE ?1
-         ORDER BY updated_at DESC"
-    ).map_err(|e| e.to_string())?;
+    let mut stmt = conn.prepare(&format!(
+        "SELECT {} FROM presets WHERE (name LIKE ?1 OR description LIKE ?1 OR tags LIKE ?1) AND deleted_at IS NULL
+         ORDER BY updated_at DESC",
+        PRESET_COLUMNS
+    )).map_err(|e| e.to_string())?;
 
-    let presets = stmt.query_map(params![search_pattern], |row| {
-        Ok(PresetConfig {
-            id: row.get(0)?,
-            name: row.get(1)?,
-            description: row.get(2)?,
-            tags: serde_json::from_str(&row.get::<_, String>(3)?).unwrap_or_default(),
-            is_favorite: row.get::<_, i32>(4)? != 0,
-            use_count: row.get(5)?,
-            created_at: row.get(6)?,
-            updated_at: row.get(7)?,
-            model: serde_json::from_str(&row.get::<_, String>(8)?).unwrap_or_else(|_| ModelConfig {
-                base_model: String::new(),
-                base_model_id: None,
-                refiner_model: String::new(),
-                refiner_model_id: None,
-                refiner_switch: 0.5,
-                loras: vec![],
-            }),
-            sampling: serde_json::from_str(&row.get::<_, String>(9)?).unwrap_or_else(|_| SamplingConfig {
-                cfg_scale: 7.0,
-                sample_sharpness: 2.0,
-                sampler: String::from("dpmpp_2m_sde_gpu"),
-                scheduler: String::from("karras"),
-                performance: String::from("Speed"),
-                steps: 30,
-            }),
-            prompt: serde_json::from_str(&row.get::<_, String>(10)?).unwrap_or_else(|_| PromptConfig {
-                positive: String::new(),
-                negative: String::new(),
-                styles: vec![],
-            }),
-            image: serde_json::from_str(&row.get::<_, String>(11)?).unwrap_or_else(|_| ImageConfig {
-                aspect_ratio: String::from("1152*896"),
-                image_count: 4,
-            }),
-            resources: row.get::<_, Option<String>>(12)?.and_then(|s| serde_json::from_str(&s).ok()),
-        })
-    }).map_err(|e| e.to_string())?;
+    let presets = stmt.query_map(params![search_pattern], PresetConfig::from_row).map_err(|e| e.to_string())?;
 
     presets.collect::<Result<Vec<_>, _>>().map_err(|e| e.to_string())
 }
@@ -267,7 +212,8 @@ pub fn get_all_tags(db: State<'_, Database>) -> Result<Vec<Tag>, String> {
     let conn = db.0.lock().map_err(|e| e.to_string())?;
     let mut stmt = conn.prepare(
         "SELECT t.id, t.name, t.color, COUNT(p.id) as count 
-         FROM tags t LEFT JOIN presets p ON ',' || p.tags || ',' LIKE '%,' || t.name || ',%' 
+         FROM tags t LEFT JOIN presets p ON ',' || p.tags || ',' LIKE '%,' || t.name || ',%' AND p.deleted_at IS NULL
+         WHERE t.deleted_at IS NULL
          GROUP BY t.id ORDER BY t.name"
     ).map_err(|e| e.to_string())?;
 
@@ -287,7 +233,12 @@ pub fn get_all_tags(db: State<'_, Database>) -> Result<Vec<Tag>, String> {
 pub fn create_tag(db: State<'_, Database>, name: String, color: String) -> Result<Tag, String> {
     let conn = db.0.lock().map_err(|e| e.to_string())?;
     let id = Uuid::new_v4().to_string();
-    
+
+    // A trashed tag would otherwise keep the name taken.
+    conn.execute(
+        "DELETE FROM tags WHERE name = ?1 AND deleted_at IS NOT NULL",
+        params![name],
+    ).map_err(|e| e.to_string())?;
     conn.execute(
         "INSERT INTO tags (id, name, color) VALUES (?1, ?2, ?3)",
         params![id, name, color],
@@ -304,7 +255,10 @@ pub fn create_tag(db: State<'_, Database>, name: String, color: String) -> Resul
 #[tauri::command]
 pub fn delete_tag(db: State<'_, Database>, id: String) -> Result<(), String> {
     let conn = db.0.lock().map_err(|e| e.to_string())?;
-    conn.execute("DELETE FROM tags WHERE id = ?1", params![id]).map_err(|e| e.to_string())?;
+    let now = Utc::now().to_rfc3339();
+    if !trash::move_to_trash(&conn, TrashKind::Tag, &id, &now).map_err(|e| e.to_string())? {
+        return Err(format!("Tag not found: {}", id));
+    }
     Ok(())
 }
 
@@ -328,97 +282,113 @@ pub fn increment_use_count(db: State<'_, Database>, id: String) -> Result<(), St
     Ok(())
 }
 
+/// Sets a preset's 1–5 rating, or clears it without one.
 #[tauri::command]
-pub fn get_all_models(db: State<'_, Database>) -> Result<Vec<ModelInfo>, String> {
+pub fn set_preset_rating(db: State<'_, Database>, id: String, rating: Option<u8>) -> Result<(), String> {
+    outcomes::validate_rating(rating)?;
     let conn = db.0.lock().map_err(|e| e.to_string())?;
-    let mut stmt = conn.prepare(
-        "SELECT id, name, file_name, model_type, description, scope, path, tags, created_at, updated_at 
-         FROM models ORDER BY updated_at DESC"
+    let updated = conn.execute(
+        "UPDATE presets SET rating = ?1, updated_at = ?2 WHERE id = ?3 AND deleted_at IS NULL",
+        params![rating, Utc::now().to_rfc3339(), id],
     ).map_err(|e| e.to_string())?;
+    if updated == 0 {
+        return Err(format!("Preset not found: {}", id));
+    }
+    Ok(())
+}
 
-    let models = stmt.query_map([], |row| {
-        Ok(ModelInfo {
-            id: row.get(0)?,
-            name: row.get(1)?,
-            file_name: row.get(2)?,
-            model_type: row.get(3)?,
-            description: row.get(4)?,
-            scope: serde_json::from_str(&row.get::<_, String>(5)?).unwrap_or_default(),
-            path: row.get(6)?,
-            tags: serde_json::from_str(&row.get::<_, String>(7)?).unwrap_or_default(),
-            created_at: row.get(8)?,
-            updated_at: row.get(9)?,
-        })
-    }).map_err(|e| e.to_string())?;
+#[tauri::command]
+pub fn set_preset_notes(db: State<'_, Database>, id: String, notes: String) -> Result<(), String> {
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+    let updated = conn.execute(
+        "UPDATE presets SET notes = ?1, updated_at = ?2 WHERE id = ?3 AND deleted_at IS NULL",
+        params![notes, Utc::now().to_rfc3339(), id],
+    ).map_err(|e| e.to_string())?;
+    if updated == 0 {
+        return Err(format!("Preset not found: {}", id));
+    }
+    Ok(())
+}
 
-    models.collect::<Result<Vec<_>, _>>().map_err(|e| e.to_string())
+/// Records whether the results of one use of a preset were kept. Defaults to
+/// now when `used_at` is not given.
+#[tauri::command]
+pub fn record_preset_outcome(
+    db: State<'_, Database>,
+    preset_id: String,
+    outcome: Outcome,
+    comment: Option<String>,
+    used_at: Option<String>,
+) -> Result<PresetOutcome, String> {
+    let used_at = match used_at {
+        Some(used_at) => outcomes::parse_used_at(&used_at)?,
+        None => Utc::now().to_rfc3339(),
+    };
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+    load_live_preset(&conn, &preset_id)?;
+    let outcome = PresetOutcome {
+        id: Uuid::new_v4().to_string(),
+        preset_id,
+        outcome,
+        comment: comment.unwrap_or_default(),
+        used_at,
+    };
+    outcomes::insert_outcome(&conn, &outcome).map_err(|e| e.to_string())?;
+    Ok(outcome)
 }
 
 #[tauri::command]
-pub fn get_presets_by_model_id(db: State<'_, Database>, model_id: String) -> Result<Vec<PresetConfig>, String> {
+pub fn get_preset_outcomes(db: State<'_, Database>, preset_id: String) -> Result<Vec<PresetOutcome>, String> {
     let conn = db.0.lock().map_err(|e| e.to_string())?;
-    
-    let mut stmt = conn.prepare(
-        "SELECT id, name, description, tags, is_favorite, use_count, created_at, updated_at, 
-                model_config, sampling_config, prompt_config, image_config, resources 
-         FROM presets ORDER BY updated_at DESC"
-    ).map_err(|e| e.to_string())?;
+    outcomes::load_outcomes(&conn, &preset_id).map_err(|e| e.to_string())
+}
 
-    let presets = stmt.query_map([], |row| {
-        Ok(PresetConfig {
-            id: row.get(0)?,
-            name: row.get(1)?,
-            description: row.get(2)?,
-            tags: serde_json::from_str(&row.get::<_, String>(3)?).unwrap_or_default(),
-            is_favorite: row.get::<_, i32>(4)? != 0,
-            use_count: row.get(5)?,
-            created_at: row.get(6)?,
-            updated_at: row.get(7)?,
-            model: serde_json::from_str(&row.get::<_, String>(8)?).unwrap_or_else(|_| ModelConfig {
-                base_model: String::new(),
-                base_model_id: None,
-                refiner_model: String::new(),
-                refiner_model_id: None,
-                refiner_switch: 0.5,
-                loras: vec![],
-            }),
-            sampling: serde_json::from_str(&row.get::<_, String>(9)?).unwrap_or_else(|_| SamplingConfig {
-                cfg_scale: 7.0,
-                sample_sharpness: 2.0,
-                sampler: String::from("dpmpp_2m_sde_gpu"),
-                scheduler: String::from("karras"),
-                performance: String::from("Speed"),
-                steps: 30,
-            }),
-            prompt: serde_json::from_str(&row.get::<_, String>(10)?).unwrap_or_else(|_| PromptConfig {
-                positive: String::new(),
-                negative: String::new(),
-                styles: vec![],
-            }),
-            image: serde_json::from_str(&row.get::<_, String>(11)?).unwrap_or_else(|_| ImageConfig {
-                aspect_ratio: String::from("1152*896"),
-                image_count: 4,
-            }),
-            resources: row.get::<_, Option<String>>(12)?.and_then(|s| serde_json::from_str(&s).ok()),
-        })
-    }).map_err(|e| e.to_string())?;
+#[tauri::command]
+pub fn delete_preset_outcome(db: State<'_, Database>, id: String) -> Result<(), String> {
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+    conn.execute("DELETE FROM preset_outcomes WHERE id = ?1", params![id]).map_err(|e| e.to_string())?;
+    Ok(())
+}
 
-    let all_presets: Vec<PresetConfig> = presets.collect::<Result<Vec<_>, _>>().map_err(|e| e.to_string())?;
-    
-    let filtered: Vec<PresetConfig> = all_presets.into_iter().filter(|p| {
-        if p.model.base_model_id.as_deref() == Some(model_id.as_str()) {
-            return true;
-        }
-        if p.model.refiner_model_id.as_deref() == Some(model_id.as_str()) {
-            return true;
-        }
-        for lora in &p.model.loras {
-            if lora.model_id.as_deref() == Some(model_id.as_str()) {
-                return true;
-            }
-        }
-        false
-    }).collect();
+/// Kept and discarded counts for every preset with recorded outcomes.
+#[tauri::command]
+pub fn get_outcome_summaries(db: State<'_, Database>) -> Result<Vec<OutcomeSummary>, String> {
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+    outcomes::load_summaries(&conn).map_err(|e| e.to_string())
+}
+
+/// Presets by rating, then by how often their results were kept. With a
+/// model, only presets using it are ranked.
+#[tauri::command]
+pub fn get_top_rated_presets(db: State<'_, Database>, model_id: Option<String>, limit: Option<usize>) -> Result<Vec<PresetRanking>, String> {
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+    let mut presets = load_all_presets(&conn).map_err(|e| e.to_string())?;
+    if let Some(model_id) = model_id {
+        let models = load_all_models(&conn).map_err(|e| e.to_string())?;
+        presets.retain(|p| embeddings::preset_references_model(p, &model_id, &models));
+    }
+    let summaries = outcomes::load_summaries(&conn).map_err(|e| e.to_string())?;
+
+    let mut rankings = outcomes::rank(presets, &summaries);
+    rankings.truncate(limit.unwrap_or(10));
+    Ok(rankings)
+}
+
+#[tauri::command]
+pub fn get_all_models(db: State<'_, Database>) -> Result<Vec<ModelInfo>, String> {
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+    load_all_models(&conn).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub fn get_presets_by_model_id(db: State<'_, Database>, model_id: String) -> Result<Vec<PresetConfig>, String> {
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+    let all_presets = load_all_presets(&conn).map_err(|e| e.to_string())?;
+    let models = load_all_models(&conn).map_err(|e| e.to_string())?;
+
+    let filtered: Vec<PresetConfig> = all_presets.into_iter()
+        .filter(|p| embeddings::preset_references_model(p, &model_id, &models))
+        .collect();
 
     Ok(filtered)
 }
@@ -438,27 +408,13 @@ pub fn check_model_usage(db: State<'_, Database>, model_id: String) -> Result<Mo
 }
 
 #[tauri::command]
-pub fn get_models_by_type(db: State<'_, Database>, model_type: String) -> Result<Vec<ModelInfo>, String> {
+pub fn get_models_by_type(db: State<'_, Database>, model_type: ModelType) -> Result<Vec<ModelInfo>, String> {
     let conn = db.0.lock().map_err(|e| e.to_string())?;
     let mut stmt = conn.prepare(
-        "SELECT id, name, file_name, model_type, description, scope, path, tags, created_at, updated_at 
-         FROM models WHERE model_type = ?1 ORDER BY updated_at DESC"
+        &format!("SELECT {} FROM models WHERE model_type = ?1 AND deleted_at IS NULL ORDER BY updated_at DESC", MODEL_COLUMNS)
     ).map_err(|e| e.to_string())?;
 
-    let models = stmt.query_map(params![model_type], |row| {
-        Ok(ModelInfo {
-            id: row.get(0)?,
-            name: row.get(1)?,
-            file_name: row.get(2)?,
-            model_type: row.get(3)?,
-            description: row.get(4)?,
-            scope: serde_json::from_str(&row.get::<_, String>(5)?).unwrap_or_default(),
-            path: row.get(6)?,
-            tags: serde_json::from_str(&row.get::<_, String>(7)?).unwrap_or_default(),
-            created_at: row.get(8)?,
-            updated_at: row.get(9)?,
-        })
-    }).map_err(|e| e.to_string())?;
+    let models = stmt.query_map(params![model_type], ModelInfo::from_row).map_err(|e| e.to_string())?;
 
     models.collect::<Result<Vec<_>, _>>().map_err(|e| e.to_string())
 }
@@ -467,24 +423,10 @@ pub fn get_models_by_type(db: State<'_, Database>, model_type: String) -> Result
 pub fn get_model_by_id(db: State<'_, Database>, id: String) -> Result<Option<ModelInfo>, String> {
     let conn = db.0.lock().map_err(|e| e.to_string())?;
     let mut stmt = conn.prepare(
-        "SELECT id, name, file_name, model_type, description, scope, path, tags, created_at, updated_at 
-         FROM models WHERE id = ?1"
+        &format!("SELECT {} FROM models WHERE id = ?1 AND deleted_at IS NULL", MODEL_COLUMNS)
     ).map_err(|e| e.to_string())?;
 
-    let result = stmt.query_row(params![id], |row| {
-        Ok(ModelInfo {
-            id: row.get(0)?,
-            name: row.get(1)?,
-            file_name: row.get(2)?,
-            model_type: row.get(3)?,
-            description: row.get(4)?,
-            scope: serde_json::from_str(&row.get::<_, String>(5)?).unwrap_or_default(),
-            path: row.get(6)?,
-            tags: serde_json::from_str(&row.get::<_, String>(7)?).unwrap_or_default(),
-            created_at: row.get(8)?,
-            updated_at: row.get(9)?,
-        })
-    });
+    let result = stmt.query_row(params![id], ModelInfo::from_row);
 
     match result {
         Ok(model) => Ok(Some(model)),
@@ -495,79 +437,204 @@ pub fn get_model_by_id(db: State<'_, Database>, id: String) -> Result<Option<Mod
 
 #[tauri::command]
 pub fn create_model(db: State<'_, Database>, model: ModelInfo) -> Result<ModelInfo, String> {
-    let conn = db.0.lock().map_err(|e| e.to_string())?;
-    let id = Uuid::new_v4().to_string();
+    let mut conn = db.0.lock().map_err(|e| e.to_string())?;
+    let now = Utc::now().to_rfc3339();
+
+    let mut model = ModelInfo {
+        id: Uuid::new_v4().to_string(),
+        created_at: now.clone(),
+        updated_at: now,
+        ..model
+    };
+    if let Some(path) = model_files::resolve_model_file(&model) {
+        metadata::apply_hints(&mut model, metadata::collect_hints(&path));
+    }
+    insert_model(&conn, &model).map_err(|e| e.to_string())?;
+
+    linking::relink_all_presets(&mut conn).map_err(|e| e.to_string())?;
+
+    Ok(model)
+}
+
+#[tauri::command]
+pub fn update_model(db: State<'_, Database>, model: ModelInfo) -> Result<ModelUpdateResult, String> {
+    let mut conn = db.0.lock().map_err(|e| e.to_string())?;
     let now = Utc::now().to_rfc3339();
     
-    let scope_json = serde_json::to_string(&model.scope).map_err(|e| e.to_string())?;
-    let tags_json = serde_json::to_string(&model.tags).map_err(|e| e.to_string())?;
+    let model = ModelInfo {
+        updated_at: now.clone(),
+        ..model
+    };
 
-    conn.execute(
-        "INSERT INTO models (id, name, file_name, model_type, description, scope, path, tags, created_at, updated_at)
-         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)",
-        params![
-            id,
-            model.name,
-            model.file_name,
-            model.model_type,
-            model.description,
-            scope_json,
-            model.path,
-            tags_json,
-            now,
-            now,
-        ],
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+
+    let previous_file_name: Option<String> = tx.query_row(
+        "SELECT file_name FROM models WHERE id = ?1",
+        params![model.id],
+        |row| row.get(0),
     ).map_err(|e| e.to_string())?;
 
-    Ok(ModelInfo {
-        id,
-        name: model.name,
-        file_name: model.file_name,
-        model_type: model.model_type,
-        description: model.description,
-        scope: model.scope,
-        path: model.path,
-        tags: model.tags,
-        created_at: now.clone(),
-        updated_at: now,
+    update_model_row(&tx, &model).map_err(|e| e.to_string())?;
+
+    let touched_presets = if previous_file_name.as_deref() != Some(model.file_name.as_str()) {
+        linking::rename_model_references(&tx, &model.id, &model.file_name, &now)
+            .map_err(|e| e.to_string())?
+    } else {
+        vec![]
+    };
+
+    tx.commit().map_err(|e| e.to_string())?;
+
+    Ok(ModelUpdateResult {
+        model,
+        touched_presets,
     })
 }
 
 #[tauri::command]
-pub fn update_model(db: State<'_, Database>, model: ModelInfo) -> Result<ModelInfo, String> {
-    let conn = db.0.lock().map_err(|e| e.to_string())?;
+pub fn delete_model(db: State<'_, Database>, id: String, strategy: Option<DeleteStrategy>) -> Result<ModelDeletionResult, String> {
+    let mut conn = db.0.lock().map_err(|e| e.to_string())?;
+    let strategy = strategy.unwrap_or_default();
     let now = Utc::now().to_rfc3339();
-    
-    let scope_json = serde_json::to_string(&model.scope).map_err(|e| e.to_string())?;
-    let tags_json = serde_json::to_string(&model.tags).map_err(|e| e.to_string())?;
 
-    conn.execute(
-        "UPDATE models SET name = ?1, file_name = ?2, model_type = ?3, description = ?4, 
-         scope = ?5, path = ?6, tags = ?7, updated_at = ?8 WHERE id = ?9",
-        params![
-            model.name,
-            model.file_name,
-            model.model_type,
-            model.description,
-            scope_json,
-            model.path,
-            tags_json,
-            now,
-            model.id,
-        ],
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+
+    let model = tx.query_row(
+        &format!("SELECT {} FROM models WHERE id = ?1 AND deleted_at IS NULL", MODEL_COLUMNS),
+        params![id],
+        ModelInfo::from_row,
     ).map_err(|e| e.to_string())?;
 
-    Ok(ModelInfo {
-        updated_at: now,
-        ..model
+    let replacement = match &strategy {
+        DeleteStrategy::Replace { replacement_id } => {
+            if *replacement_id == id {
+                return Err("A model cannot replace itself".to_string());
+            }
+            Some(tx.query_row(
+                &format!("SELECT {} FROM models WHERE id = ?1 AND deleted_at IS NULL", MODEL_COLUMNS),
+                params![replacement_id],
+                ModelInfo::from_row,
+            ).map_err(|e| format!("Replacement model not found: {}", e))?)
+        }
+        _ => None,
+    };
+
+    let models = load_all_models(&tx).map_err(|e| e.to_string())?;
+    let users: Vec<PresetConfig> = load_all_presets(&tx).map_err(|e| e.to_string())?
+        .into_iter()
+        .filter(|p| embeddings::preset_references_model(p, &id, &models))
+        .collect();
+
+    if !users.is_empty() && strategy == DeleteStrategy::Refuse {
+        let names: Vec<&str> = users.iter().map(|p| p.name.as_str()).collect();
+        return Err(format!(
+            "Model is used by {} preset(s): {}",
+            users.len(),
+            names.join(", ")
+        ));
+    }
+    if let Some(replacement) = &replacement {
+        for preset in &users {
+            let misfit = linking::slots_using(&preset.model, &id).into_iter()
+                .find(|slot| !slot.accepts(replacement.model_type));
+            if let Some(slot) = misfit {
+                return Err(format!(
+                    "{} is a {} and cannot be the {} of {}",
+                    replacement.name, replacement.model_type, slot.label(), preset.name
+                ));
+            }
+            if replacement.model_type != ModelType::Embedding
+                && embeddings::prompt_references_model(&preset.prompt, &id, &models)
+            {
+                return Err(format!(
+                    "{} is a {} and cannot replace the embedding in the prompts of {}",
+                    replacement.name, replacement.model_type, preset.name
+                ));
+            }
+        }
+    }
+
+    // Only `Replace` rewrites embedding tokens; otherwise they stay in the
+    // prompts and `lint_preset` reports them as missing.
+    let mut changed_presets = Vec::new();
+    let mut touched_presets = Vec::new();
+    for mut preset in users {
+        let mut changes = linking::apply_delete_strategy(&mut preset.model, &id, &strategy, replacement.as_ref());
+        if let Some(replacement) = &replacement {
+            changes.extend(embeddings::replace_embedding(&mut preset.prompt, &id, replacement, &models));
+        }
+        if changes.is_empty() {
+            continue;
+        }
+        preset.updated_at = now.clone();
+        revisions::save_preset(&tx, &preset, &now).map_err(|e| e.to_string())?;
+        changed_presets.push(PresetReferenceChanges {
+            preset_id: preset.id.clone(),
+            changes,
+        });
+        touched_presets.push(PresetSummary {
+            id: preset.id,
+            name: preset.name,
+        });
+    }
+
+    trash::move_to_trash(&tx, TrashKind::Model, &id, &now).map_err(|e| e.to_string())?;
+
+    let undo_id = Uuid::new_v4().to_string();
+    let payload = serde_json::to_string(&ModelDeletionUndo {
+        model,
+        presets: changed_presets,
+    }).map_err(|e| e.to_string())?;
+    tx.execute(
+        "INSERT INTO undo_records (id, action, payload, created_at) VALUES (?1, ?2, ?3, ?4)",
+        params![undo_id, "delete_model", payload, now],
+    ).map_err(|e| e.to_string())?;
+
+    tx.commit().map_err(|e| e.to_string())?;
+
+    Ok(ModelDeletionResult {
+        undo_id,
+        touched_presets,
     })
 }
 
+/// Brings a deleted model back and reverts the references its deletion
+/// changed, except those edited since.
 #[tauri::command]
-pub fn delete_model(db: State<'_, Database>, id: String) -> Result<(), String> {
-    let conn = db.0.lock().map_err(|e| e.to_string())?;
-    conn.execute("DELETE FROM models WHERE id = ?1", params![id]).map_err(|e| e.to_string())?;
-    Ok(())
+pub fn undo_model_deletion(db: State<'_, Database>, undo_id: String) -> Result<ModelInfo, String> {
+    let mut conn = db.0.lock().map_err(|e| e.to_string())?;
+    let now = Utc::now().to_rfc3339();
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+
+    let payload: String = tx.query_row(
+        "SELECT payload FROM undo_records WHERE id = ?1 AND action = 'delete_model'",
+        params![undo_id],
+        |row| row.get(0),
+    ).map_err(|e| e.to_string())?;
+    let undo: ModelDeletionUndo = serde_json::from_str(&payload).map_err(|e| e.to_string())?;
+
+    // The model may have been purged from the trash since.
+    if !trash::restore(&tx, TrashKind::Model, &undo.model.id).map_err(|e| e.to_string())? {
+        insert_model(&tx, &undo.model).map_err(|e| e.to_string())?;
+    }
+    for changed in &undo.presets {
+        let Some(mut preset) = tx.query_row(
+            &format!("SELECT {} FROM presets WHERE id = ?1", PRESET_COLUMNS),
+            params![changed.preset_id],
+            PresetConfig::from_row,
+        ).optional().map_err(|e| e.to_string())? else {
+            continue;
+        };
+        if linking::revert_reference_changes(&mut preset, &changed.changes) {
+            preset.updated_at = now.clone();
+            revisions::save_preset(&tx, &preset, &now).map_err(|e| e.to_string())?;
+        }
+    }
+
+    tx.execute("DELETE FROM undo_records WHERE id = ?1", params![undo_id]).map_err(|e| e.to_string())?;
+    tx.commit().map_err(|e| e.to_string())?;
+
+    Ok(undo.model)
 }
 
 #[tauri::command]
@@ -576,25 +643,1288 @@ pub fn search_models(db: State<'_, Database>, query: String) -> Result<Vec<Model
     let search_pattern = format!("%{}%", query);
     
     let mut stmt = conn.prepare(
-        "SELECT id, name, file_name, model_type, description, scope, path, tags, created_at, updated_at 
-         FROM models WHERE name LIKE ?1 OR description LIKE ?1 OR scope LIKE ?1 OR tags LIKE ?1
-         ORDER BY updated_at DESC"
+        &format!(
+            "SELECT {} FROM models
+             WHERE (name LIKE ?1 OR description LIKE ?1 OR scope LIKE ?1 OR tags LIKE ?1) AND deleted_at IS NULL
+             ORDER BY updated_at DESC",
+            MODEL_COLUMNS
+        )
     ).map_err(|e| e.to_string())?;
 
-    let models = stmt.query_map(params![search_pattern], |row| {
-        Ok(ModelInfo {
-            id: row.get(0)?,
-            name: row.get(1)?,
-            file_name: row.get(2)?,
-            model_type: row.get(3)?,
-            description: row.get(4)?,
-            scope: serde_json::from_str(&row.get::<_, String>(5)?).unwrap_or_default(),
-            path: row.get(6)?,
-            tags: serde_json::from_str(&row.get::<_, String>(7)?).unwrap_or_default(),
-            created_at: row.get(8)?,
-            updated_at: row.get(9)?,
-        })
-    }).map_err(|e| e.to_string())?;
+    let models = stmt.query_map(params![search_pattern], ModelInfo::from_row).map_err(|e| e.to_string())?;
 
     models.collect::<Result<Vec<_>, _>>().map_err(|e| e.to_string())
 }
+
+#[tauri::command]
+pub fn relink_preset_models(db: State<'_, Database>) -> Result<RelinkReport, String> {
+    let mut conn = db.0.lock().map_err(|e| e.to_string())?;
+    linking::relink_all_presets(&mut conn).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub fn link_preset_model(db: State<'_, Database>, preset_id: String, slot: ModelSlot, model_id: String) -> Result<PresetConfig, String> {
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+
+    let model = conn.query_row(
+        &format!("SELECT {} FROM models WHERE id = ?1 AND deleted_at IS NULL", MODEL_COLUMNS),
+        params![model_id],
+        ModelInfo::from_row,
+    ).optional().map_err(|e| e.to_string())?
+        .ok_or_else(|| format!("Model not found: {}", model_id))?;
+    if !slot.accepts(model.model_type) {
+        return Err(format!("{} is a {} and cannot be the {}", model.name, model.model_type, slot.label()));
+    }
+
+    let mut preset = conn.query_row(
+        &format!("SELECT {} FROM presets WHERE id = ?1 AND deleted_at IS NULL", PRESET_COLUMNS),
+        params![preset_id],
+        PresetConfig::from_row,
+    ).optional().map_err(|e| e.to_string())?
+        .ok_or_else(|| format!("Preset not found: {}", preset_id))?;
+
+    match slot {
+        ModelSlot::Base => preset.model.base_model_id = Some(model_id),
+        ModelSlot::Refiner => preset.model.refiner_model_id = Some(model_id),
+        ModelSlot::Vae => preset.model.vae_model_id = Some(model_id),
+        ModelSlot::Lora { index } => {
+            let lora = preset.model.loras.get_mut(index)
+                .ok_or_else(|| format!("LoRA index out of range: {}", index))?;
+            lora.model_id = Some(model_id);
+        }
+    }
+
+    let now = Utc::now().to_rfc3339();
+    preset.updated_at = now.clone();
+    revisions::save_preset(&conn, &preset, &now).map_err(|e| e.to_string())?;
+    Ok(preset)
+}
+
+#[tauri::command]
+pub async fn find_duplicate_models(app: AppHandle, directories: Option<Vec<String>>) -> Result<DuplicateReport, String> {
+    tauri::async_runtime::spawn_blocking(move || scan_duplicate_models(&app.state::<Database>(), directories))
+        .await
+        .map_err(|e| e.to_string())?
+}
+
+/// Hashes model files to find duplicates. Blocking, as it reads whole files.
+fn scan_duplicate_models(db: &Database, directories: Option<Vec<String>>) -> Result<DuplicateReport, String> {
+    let (models, presets) = {
+        let conn = db.0.lock().map_err(|e| e.to_string())?;
+        (
+            load_all_models(&conn).map_err(|e| e.to_string())?,
+            load_all_presets(&conn).map_err(|e| e.to_string())?,
+        )
+    };
+
+    let mut report = DuplicateReport::default();
+    let mut owners: HashMap<PathBuf, Vec<ModelInfo>> = HashMap::new();
+    for model in models.iter().cloned() {
+        match model_files::resolve_model_file(&model) {
+            Some(path) => {
+                let key = path.canonicalize().unwrap_or(path);
+                owners.entry(key).or_default().push(model);
+            }
+            None => report.missing_files.push(ModelCandidate {
+                id: model.id,
+                name: model.name,
+                file_name: model.file_name,
+            }),
+        }
+    }
+
+    let mut files: Vec<PathBuf> = owners.keys().cloned().collect();
+    for dir in directories.unwrap_or_default() {
+        let mut found = Vec::new();
+        model_files::scan_model_files(&PathBuf::from(&dir), &mut found)
+            .map_err(|e| format!("Failed to scan {}: {}", dir, e))?;
+        for path in found {
+            let key = path.canonicalize().unwrap_or(path);
+            if !owners.contains_key(&key) && !files.contains(&key) {
+                files.push(key);
+            }
+        }
+    }
+
+    let groups = model_files::group_by_content(files, |path, stamp| cached_sha256(db, path, stamp));
+
+    // Two rows pointing at the very same file are duplicates as well.
+    for (path, rows) in owners.iter().filter(|(_, rows)| rows.len() > 1) {
+        if !groups.iter().any(|(_, _, paths)| paths.contains(path)) {
+            let stamp = model_files::file_stamp(path).map_err(|e| e.to_string())?;
+            let hash = cached_sha256(db, path, &stamp).map_err(|e| e.to_string())?;
+            report.groups.push(DuplicateGroup {
+                hash,
+                size: stamp.size,
+                models: rows.iter().map(|m| duplicate_entry(m, path, &presets, &models)).collect(),
+                unregistered_files: vec![],
+            });
+        }
+    }
+
+    for (hash, size, paths) in groups {
+        let mut group = DuplicateGroup {
+            hash,
+            size,
+            models: vec![],
+            unregistered_files: vec![],
+        };
+        for path in paths {
+            match owners.get(&path) {
+                Some(rows) => group.models.extend(rows.iter().map(|m| duplicate_entry(m, &path, &presets, &models))),
+                None => group.unregistered_files.push(path.to_string_lossy().to_string()),
+            }
+        }
+        report.groups.push(group);
+    }
+
+    Ok(report)
+}
+
+fn cached_sha256(db: &Database, path: &std::path::Path, stamp: &model_files::FileStamp) -> std::io::Result<String> {
+    {
+        let conn = db.0.lock().map_err(|e| std::io::Error::other(e.to_string()))?;
+        if let Ok(Some(hash)) = model_files::cached_hash(&conn, path, stamp) {
+            return Ok(hash);
+        }
+    }
+    let hash = model_files::sha256_file(path)?;
+    let conn = db.0.lock().map_err(|e| std::io::Error::other(e.to_string()))?;
+    model_files::store_hash(&conn, path, stamp, &hash).ok();
+    Ok(hash)
+}
+
+/// Hashes the file of a model, reusing the cached digest while it is unchanged.
+/// Returns the file it hashed along with the digest.
+fn model_file_hash(db: &Database, model: &ModelInfo) -> Result<((PathBuf, model_files::FileStamp), String), String> {
+    let path = model_files::resolve_model_file(model)
+        .ok_or_else(|| format!("Model file not found for {}", model.name))?;
+    let path = path.canonicalize().unwrap_or(path);
+    let stamp = model_files::file_stamp(&path).map_err(|e| e.to_string())?;
+    let hash = cached_sha256(db, &path, &stamp).map_err(|e| e.to_string())?;
+    Ok(((path, stamp), hash))
+}
+
+fn duplicate_entry(model: &ModelInfo, path: &std::path::Path, presets: &[PresetConfig], models: &[ModelInfo]) -> DuplicateModelEntry {
+    DuplicateModelEntry {
+        model: model.clone(),
+        file_path: path.to_string_lossy().to_string(),
+        presets: presets.iter()
+            .filter(|p| embeddings::preset_references_model(p, &model.id, models))
+            .map(|p| PresetSummary {
+                id: p.id.clone(),
+                name: p.name.clone(),
+            })
+            .collect(),
+    }
+}
+
+#[tauri::command]
+pub async fn merge_duplicate_models(app: AppHandle, canonical_id: String, duplicate_ids: Vec<String>) -> Result<ModelMergeResult, String> {
+    tauri::async_runtime::spawn_blocking(move || merge_models(&app, &app.state::<Database>(), canonical_id, duplicate_ids))
+        .await
+        .map_err(|e| e.to_string())?
+}
+
+/// Replaces duplicates of the canonical model in every preset and removes
+/// them. Files are hashed before the database is locked; the rewrite only
+/// goes ahead when none of them changed in the meantime.
+fn merge_models(app: &AppHandle, db: &Database, canonical_id: String, duplicate_ids: Vec<String>) -> Result<ModelMergeResult, String> {
+    if duplicate_ids.contains(&canonical_id) {
+        return Err("The canonical model cannot also be a duplicate".to_string());
+    }
+    let (_, thumbnail_dir) = model_preview_dirs(app)?;
+
+    let models = {
+        let conn = db.0.lock().map_err(|e| e.to_string())?;
+        load_all_models(&conn).map_err(|e| e.to_string())?
+    };
+    let find = |id: &String, role: &str| models.iter()
+        .find(|m| &m.id == id)
+        .cloned()
+        .ok_or_else(|| format!("{} model not found: {}", role, id));
+    let canonical = find(&canonical_id, "Canonical")?;
+    let duplicates = duplicate_ids.iter()
+        .map(|id| find(id, "Duplicate"))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let (canonical_file, canonical_hash) = model_file_hash(db, &canonical)?;
+    let mut files = vec![canonical_file];
+    for duplicate in &duplicates {
+        if duplicate.model_type != canonical.model_type {
+            return Err(format!(
+                "{} is a {} and cannot be merged into {}, a {}",
+                duplicate.name, duplicate.model_type, canonical.name, canonical.model_type
+            ));
+        }
+        let (file, hash) = model_file_hash(db, duplicate)?;
+        if hash != canonical_hash {
+            return Err(format!("{} does not have the same content as {}", duplicate.name, canonical.name));
+        }
+        files.push(file);
+    }
+
+    let mut conn = db.0.lock().map_err(|e| e.to_string())?;
+    let now = Utc::now().to_rfc3339();
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+
+    let models = load_all_models(&tx).map_err(|e| e.to_string())?;
+    for (model, (path, stamp)) in std::iter::once(&canonical).chain(&duplicates).zip(&files) {
+        let current = models.iter().find(|m| m.id == model.id)
+            .ok_or_else(|| format!("Model not found: {}", model.name))?;
+        let unchanged = current.path == model.path
+            && current.file_name == model.file_name
+            && model_files::file_stamp(path).is_ok_and(|s| s == *stamp);
+        if !unchanged {
+            return Err(format!("{} changed while it was being hashed; try again", model.name));
+        }
+    }
+
+    let strategy = DeleteStrategy::Replace {
+        replacement_id: canonical.id.clone(),
+    };
+    let mut touched_presets = Vec::new();
+    for mut preset in load_all_presets(&tx).map_err(|e| e.to_string())? {
+        let mut changed = false;
+        for duplicate_id in &duplicate_ids {
+            if embeddings::preset_references_model(&preset, duplicate_id, &models) {
+                linking::apply_delete_strategy(&mut preset.model, duplicate_id, &strategy, Some(&canonical));
+                embeddings::replace_embedding(&mut preset.prompt, duplicate_id, &canonical, &models);
+                changed = true;
+            }
+        }
+        if changed {
+            preset.updated_at = now.clone();
+            revisions::save_preset(&tx, &preset, &now).map_err(|e| e.to_string())?;
+            touched_presets.push(PresetSummary {
+                id: preset.id,
+                name: preset.name,
+            });
+        }
+    }
+
+    let mut removed_model_ids = Vec::new();
+    for duplicate in &duplicates {
+        tx.execute("DELETE FROM models WHERE id = ?1", params![duplicate.id])
+            .map_err(|e| e.to_string())?;
+        tx.execute("DELETE FROM model_family_versions WHERE model_id = ?1", params![duplicate.id])
+            .map_err(|e| e.to_string())?;
+        removed_model_ids.push(duplicate.id.clone());
+    }
+
+    tx.commit().map_err(|e| e.to_string())?;
+
+    for duplicate in &duplicates {
+        if let Some(preview) = &duplicate.preview_image {
+            std::fs::remove_file(preview).ok();
+        }
+        thumbnails::remove_thumbnails(&thumbnail_dir, &duplicate.id);
+    }
+
+    Ok(ModelMergeResult {
+        removed_model_ids,
+        touched_presets,
+    })
+}
+
+#[tauri::command]
+pub fn import_model_hints(db: State<'_, Database>, id: String) -> Result<ModelInfo, String> {
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+    let mut model = conn.query_row(
+        &format!("SELECT {} FROM models WHERE id = ?1", MODEL_COLUMNS),
+        params![id],
+        ModelInfo::from_row,
+    ).map_err(|e| e.to_string())?;
+
+    let path = model_files::resolve_model_file(&model)
+        .ok_or_else(|| format!("Model file not found for {}", model.name))?;
+    if !metadata::apply_hints(&mut model, metadata::collect_hints(&path)) {
+        return Ok(model);
+    }
+
+    model.updated_at = Utc::now().to_rfc3339();
+    update_model_row(&conn, &model).map_err(|e| e.to_string())?;
+
+    Ok(model)
+}
+
+#[tauri::command]
+pub fn lint_preset(db: State<'_, Database>, preset: PresetConfig) -> Result<Vec<LintWarning>, String> {
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+    let models = load_all_models(&conn).map_err(|e| e.to_string())?;
+    Ok(lint::lint_preset(&preset, &models))
+}
+
+#[tauri::command]
+pub fn preview_sidecar_import(db: State<'_, Database>, model_ids: Option<Vec<String>>) -> Result<Vec<SidecarImportPreview>, String> {
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+    let models = load_all_models(&conn).map_err(|e| e.to_string())?;
+
+    let previews = models.iter()
+        .filter(|m| match &model_ids {
+            Some(ids) => ids.contains(&m.id),
+            None => true,
+        })
+        .filter_map(|model| {
+            let path = model_files::resolve_model_file(model)?;
+            let meta = metadata::read_sidecars(&path);
+            if meta.files.is_empty() {
+                return None;
+            }
+            Some(SidecarImportPreview {
+                model_id: model.id.clone(),
+                model_name: model.name.clone(),
+                changes: metadata::diff_sidecar(model, &meta),
+                files: meta.files,
+            })
+        })
+        .collect();
+
+    Ok(previews)
+}
+
+#[tauri::command]
+pub fn apply_sidecar_import(db: State<'_, Database>, model_id: String, fields: Option<Vec<String>>) -> Result<ModelInfo, String> {
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+    let mut model = conn.query_row(
+        &format!("SELECT {} FROM models WHERE id = ?1", MODEL_COLUMNS),
+        params![model_id],
+        ModelInfo::from_row,
+    ).map_err(|e| e.to_string())?;
+
+    let path = model_files::resolve_model_file(&model)
+        .ok_or_else(|| format!("Model file not found for {}", model.name))?;
+    let meta = metadata::read_sidecars(&path);
+
+    // Without an explicit selection only fields the user has not filled in are taken.
+    let fields = fields.unwrap_or_else(|| {
+        metadata::diff_sidecar(&model, &meta).into_iter()
+            .filter(|c| !c.overwrites)
+            .map(|c| c.field)
+            .collect()
+    });
+
+    metadata::apply_sidecar(&mut model, meta, &fields);
+    model.updated_at = Utc::now().to_rfc3339();
+    update_model_row(&conn, &model).map_err(|e| e.to_string())?;
+
+    Ok(model)
+}
+
+fn model_preview_dirs(app: &AppHandle) -> Result<(PathBuf, PathBuf), String> {
+    let app_data_dir = app.path().app_data_dir().map_err(|e| e.to_string())?;
+    Ok((app_data_dir.join("previews"), app_data_dir.join("thumbnails").join("models")))
+}
+
+fn store_model_preview(app: &AppHandle, model: &mut ModelInfo, source: &Path) -> Result<(), String> {
+    let (preview_dir, thumbnail_dir) = model_preview_dirs(app)?;
+    let stored = thumbnails::replace_image(source, &preview_dir, &thumbnail_dir, &model.id)?;
+    let stored_path = stored.to_string_lossy().to_string();
+    if let Some(old) = model.preview_image.as_ref().filter(|old| **old != stored_path) {
+        std::fs::remove_file(old).ok();
+    }
+    model.preview_image = Some(stored_path);
+    Ok(())
+}
+
+#[tauri::command]
+pub fn set_model_preview(app: AppHandle, db: State<'_, Database>, model_id: String, source_path: String) -> Result<ModelInfo, String> {
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+    let mut model = conn.query_row(
+        &format!("SELECT {} FROM models WHERE id = ?1", MODEL_COLUMNS),
+        params![model_id],
+        ModelInfo::from_row,
+    ).map_err(|e| e.to_string())?;
+
+    store_model_preview(&app, &mut model, Path::new(&source_path))?;
+    model.updated_at = Utc::now().to_rfc3339();
+    update_model_row(&conn, &model).map_err(|e| e.to_string())?;
+
+    Ok(model)
+}
+
+#[tauri::command]
+pub fn clear_model_preview(app: AppHandle, db: State<'_, Database>, model_id: String) -> Result<ModelInfo, String> {
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+    let mut model = conn.query_row(
+        &format!("SELECT {} FROM models WHERE id = ?1", MODEL_COLUMNS),
+        params![model_id],
+        ModelInfo::from_row,
+    ).map_err(|e| e.to_string())?;
+
+    let (_, thumbnail_dir) = model_preview_dirs(&app)?;
+    if let Some(old) = model.preview_image.take() {
+        std::fs::remove_file(old).ok();
+    }
+    thumbnails::remove_thumbnails(&thumbnail_dir, &model.id);
+    model.updated_at = Utc::now().to_rfc3339();
+    update_model_row(&conn, &model).map_err(|e| e.to_string())?;
+
+    Ok(model)
+}
+
+/// Picks up `*.preview.png` style sidecars for every model that has no preview yet.
+#[tauri::command]
+pub fn import_model_previews(app: AppHandle, db: State<'_, Database>) -> Result<Vec<ModelInfo>, String> {
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+    let now = Utc::now().to_rfc3339();
+    let mut imported = Vec::new();
+
+    for mut model in load_all_models(&conn).map_err(|e| e.to_string())? {
+        if model.preview_image.is_some() {
+            continue;
+        }
+        let Some(sidecar) = model_files::resolve_model_file(&model)
+            .and_then(|path| thumbnails::find_preview_sidecar(&path)) else {
+            continue;
+        };
+
+        // Images that fail to decode are left for the user to replace by hand.
+        if store_model_preview(&app, &mut model, &sidecar).is_err() {
+            continue;
+        }
+        model.updated_at = now.clone();
+        update_model_row(&conn, &model).map_err(|e| e.to_string())?;
+        imported.push(model);
+    }
+
+    Ok(imported)
+}
+
+/// Returns the model's preview thumbnail as a data URL, or `None` when it has no preview.
+#[tauri::command]
+pub fn get_model_thumbnail(app: AppHandle, db: State<'_, Database>, model_id: String, size: u32) -> Result<Option<String>, String> {
+    let preview_image: Option<String> = {
+        let conn = db.0.lock().map_err(|e| e.to_string())?;
+        conn.query_row(
+            "SELECT preview_image FROM models WHERE id = ?1",
+            params![model_id],
+            |row| row.get(0),
+        ).map_err(|e| e.to_string())?
+    };
+
+    let Some(preview_image) = preview_image else {
+        return Ok(None);
+    };
+    let preview_image = PathBuf::from(preview_image);
+    if !preview_image.is_file() {
+        return Ok(None);
+    }
+
+    let (_, thumbnail_dir) = model_preview_dirs(&app)?;
+    thumbnails::thumbnail_data_url(&preview_image, &thumbnail_dir, &model_id, size).map(Some)
+}
+
+fn gallery_dirs(app: &AppHandle, preset_id: &str) -> Result<(PathBuf, PathBuf), String> {
+    let app_data_dir = app.path().app_data_dir().map_err(|e| e.to_string())?;
+    Ok((app_data_dir.join("gallery").join(preset_id), app_data_dir.join("thumbnails").join("gallery")))
+}
+
+/// Attaches images to a preset's gallery. With `copy` the files are copied into the
+/// app data dir, otherwise the gallery only references them where they are.
+#[tauri::command]
+pub fn add_preset_images(app: AppHandle, db: State<'_, Database>, preset_id: String, paths: Vec<String>, copy: bool) -> Result<Vec<PresetImage>, String> {
+    let now = Utc::now().to_rfc3339();
+    let (gallery_dir, thumbnail_dir) = gallery_dirs(&app, &preset_id)?;
+
+    {
+        let conn = db.0.lock().map_err(|e| e.to_string())?;
+        ensure_live_preset(&conn, &preset_id)?;
+    }
+
+    let sources: Vec<PathBuf> = paths.into_iter().map(PathBuf::from).collect();
+    if let Some(source) = sources.iter().find(|s| !s.is_file() || !thumbnails::is_image_file(s)) {
+        return Err(format!("Not an image file: {}", source.display()));
+    }
+
+    // Copies and thumbnails are written without holding the database lock.
+    let mut added = Vec::new();
+    for source in sources {
+        let id = Uuid::new_v4().to_string();
+        let path = match store_gallery_image(&source, copy, &gallery_dir, &thumbnail_dir, &id) {
+            Ok(path) => path,
+            Err(e) => {
+                added.iter().for_each(|image| gallery::remove_image_files(image, &thumbnail_dir));
+                return Err(e);
+            }
+        };
+        added.push(PresetImage {
+            id,
+            preset_id: preset_id.clone(),
+            path: path.to_string_lossy().to_string(),
+            is_copy: copy,
+            is_cover: false,
+            notes: String::new(),
+            rating: None,
+            sort_order: 0,
+            created_at: now.clone(),
+        });
+    }
+
+    let inserted = insert_gallery_images(&db, &preset_id, &mut added);
+    if inserted.is_err() {
+        added.iter().for_each(|image| gallery::remove_image_files(image, &thumbnail_dir));
+    }
+    inserted.map(|_| added)
+}
+
+/// Copies an image into the gallery when asked to and renders its thumbnails,
+/// leaving no files behind on failure.
+fn store_gallery_image(source: &Path, copy: bool, gallery_dir: &Path, thumbnail_dir: &Path, id: &str) -> Result<PathBuf, String> {
+    let path = if copy {
+        thumbnails::store_image(source, gallery_dir, id)?
+    } else {
+        source.to_path_buf()
+    };
+    if let Err(e) = thumbnails::generate_thumbnails(&path, thumbnail_dir, id) {
+        if copy {
+            std::fs::remove_file(&path).ok();
+        }
+        thumbnails::remove_thumbnails(thumbnail_dir, id);
+        return Err(e);
+    }
+    Ok(path)
+}
+
+fn ensure_live_preset(conn: &Connection, preset_id: &str) -> Result<(), String> {
+    conn.query_row(
+        "SELECT id FROM presets WHERE id = ?1 AND deleted_at IS NULL",
+        params![preset_id],
+        |row| row.get::<_, String>(0),
+    ).map_err(|e| format!("Preset not found: {}", e))?;
+    Ok(())
+}
+
+/// Appends images to the end of a preset's gallery in one transaction, making
+/// the first one the cover when the gallery has none.
+fn insert_gallery_images(db: &Database, preset_id: &str, images: &mut [PresetImage]) -> Result<(), String> {
+    let mut conn = db.0.lock().map_err(|e| e.to_string())?;
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+    ensure_live_preset(&tx, preset_id)?;
+
+    let existing = gallery::load_preset_images(&tx, preset_id).map_err(|e| e.to_string())?;
+    let mut has_cover = existing.iter().any(|i| i.is_cover);
+    let next_order = existing.iter().map(|i| i.sort_order + 1).max().unwrap_or(0);
+    for (offset, image) in images.iter_mut().enumerate() {
+        image.is_cover = !has_cover;
+        image.sort_order = next_order + offset as i32;
+        gallery::insert_preset_image(&tx, image).map_err(|e| e.to_string())?;
+        has_cover = true;
+    }
+    tx.commit().map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub fn get_preset_images(db: State<'_, Database>, preset_id: String) -> Result<Vec<PresetImage>, String> {
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+    gallery::load_preset_images(&conn, &preset_id).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub fn update_preset_image(db: State<'_, Database>, image_id: String, notes: String, rating: Option<u8>) -> Result<PresetImage, String> {
+    outcomes::validate_rating(rating)?;
+
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+    conn.execute(
+        "UPDATE preset_images SET notes = ?1, rating = ?2 WHERE id = ?3",
+        params![notes, rating, image_id],
+    ).map_err(|e| e.to_string())?;
+
+    gallery::load_preset_image(&conn, &image_id).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub fn set_preset_cover(db: State<'_, Database>, preset_id: String, image_id: String) -> Result<(), String> {
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+    let image = gallery::load_preset_image(&conn, &image_id).map_err(|e| e.to_string())?;
+    if image.preset_id != preset_id {
+        return Err("Image does not belong to this preset".to_string());
+    }
+    gallery::set_cover(&conn, &preset_id, &image_id).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub fn remove_preset_image(app: AppHandle, db: State<'_, Database>, image_id: String) -> Result<(), String> {
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+    let image = gallery::load_preset_image(&conn, &image_id).map_err(|e| e.to_string())?;
+    let (_, thumbnail_dir) = gallery_dirs(&app, &image.preset_id)?;
+
+    conn.execute("DELETE FROM preset_images WHERE id = ?1", params![image_id]).map_err(|e| e.to_string())?;
+
+    if image.is_cover {
+        let remaining = gallery::load_preset_images(&conn, &image.preset_id).map_err(|e| e.to_string())?;
+        if let Some(next) = remaining.first() {
+            gallery::set_cover(&conn, &image.preset_id, &next.id).map_err(|e| e.to_string())?;
+        }
+    }
+
+    gallery::remove_image_files(&image, &thumbnail_dir);
+    Ok(())
+}
+
+/// Returns `None` when a referenced image has been moved or deleted since it was attached.
+#[tauri::command]
+pub fn get_preset_image_thumbnail(app: AppHandle, db: State<'_, Database>, image_id: String, size: u32) -> Result<Option<String>, String> {
+    let image = {
+        let conn = db.0.lock().map_err(|e| e.to_string())?;
+        gallery::load_preset_image(&conn, &image_id).map_err(|e| e.to_string())?
+    };
+
+    let path = PathBuf::from(&image.path);
+    if !path.is_file() {
+        return Ok(None);
+    }
+
+    let (_, thumbnail_dir) = gallery_dirs(&app, &image.preset_id)?;
+    thumbnails::thumbnail_data_url(&path, &thumbnail_dir, &image.id, size).map(Some)
+}
+
+#[tauri::command]
+pub fn get_preset_cover_thumbnail(app: AppHandle, db: State<'_, Database>, preset_id: String, size: u32) -> Result<Option<String>, String> {
+    let cover: Option<String> = {
+        let conn = db.0.lock().map_err(|e| e.to_string())?;
+        conn.query_row(
+            "SELECT id FROM preset_images WHERE preset_id = ?1 AND is_cover = 1",
+            params![preset_id],
+            |row| row.get(0),
+        ).optional().map_err(|e| e.to_string())?
+    };
+
+    match cover {
+        Some(image_id) => get_preset_image_thumbnail(app, db, image_id, size),
+        None => Ok(None),
+    }
+}
+
+#[tauri::command]
+pub fn get_fooocus_paths(db: State<'_, Database>) -> Result<FooocusPaths, String> {
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+    let paths = get_setting(&conn, downloads::FOOOCUS_PATHS_SETTING).map_err(|e| e.to_string())?;
+    Ok(paths.and_then(|p| serde_json::from_str(&p).ok()).unwrap_or_default())
+}
+
+#[tauri::command]
+pub fn set_fooocus_paths(db: State<'_, Database>, paths: FooocusPaths) -> Result<(), String> {
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+    let json = serde_json::to_string(&paths).map_err(|e| e.to_string())?;
+    set_setting(&conn, downloads::FOOOCUS_PATHS_SETTING, &json).map_err(|e| e.to_string())
+}
+
+/// Downloads the files listed in a preset's resource maps into the Fooocus folders,
+/// emitting `download-progress` events, and registers new files as models.
+#[tauri::command]
+pub async fn download_resources(
+    app: AppHandle,
+    db: State<'_, Database>,
+    resources: ResourceDownloads,
+    concurrency: Option<usize>,
+) -> Result<DownloadReport, String> {
+    let requests = downloads::requests_from_resources(&resources);
+    let paths = get_fooocus_paths(db.clone())?;
+
+    let emitter = app.clone();
+    let results = tauri::async_runtime::spawn_blocking(move || {
+        downloads::run_downloads(
+            requests,
+            &paths,
+            concurrency.unwrap_or(downloads::DEFAULT_CONCURRENCY),
+            |progress| {
+                emitter.emit(downloads::PROGRESS_EVENT, progress).ok();
+            },
+        )
+    }).await.map_err(|e| e.to_string())?;
+
+    let mut conn = db.0.lock().map_err(|e| e.to_string())?;
+    let existing = load_all_models(&conn).map_err(|e| e.to_string())?;
+    let now = Utc::now().to_rfc3339();
+    let mut registered = Vec::new();
+
+    for result in &results {
+        let Some(path) = result.path.as_ref().map(PathBuf::from) else {
+            continue;
+        };
+        let model_type = result.request.kind.model_type();
+        let known = existing.iter().chain(registered.iter()).any(|m: &ModelInfo| {
+            m.file_name == result.request.file_name && m.model_type == model_type
+        });
+        if known {
+            continue;
+        }
+
+        let mut model = ModelInfo {
+            id: Uuid::new_v4().to_string(),
+            name: path.file_stem().map(|s| s.to_string_lossy().to_string()).unwrap_or_default(),
+            file_name: result.request.file_name.clone(),
+            model_type,
+            description: String::new(),
+            scope: vec![],
+            path: path.to_string_lossy().to_string(),
+            tags: vec![],
+            created_at: now.clone(),
+            updated_at: now.clone(),
+            trigger_words: vec![],
+            recommended_weight_min: None,
+            recommended_weight_max: None,
+            recommended_base_model: None,
+            source_url: Some(result.request.url.clone()),
+            preview_image: None,
+        };
+        metadata::apply_hints(&mut model, metadata::collect_hints(&path));
+        insert_model(&conn, &model).map_err(|e| e.to_string())?;
+        registered.push(model);
+    }
+
+    if !registered.is_empty() {
+        linking::relink_all_presets(&mut conn).map_err(|e| e.to_string())?;
+    }
+
+    Ok(DownloadReport {
+        results,
+        registered,
+    })
+}
+
+/// Lists the download entries that could not be converted when resource maps became typed.
+#[tauri::command]
+pub fn get_resource_issues(db: State<'_, Database>) -> Result<Vec<ResourceIssue>, String> {
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+    resources::load_issues(&conn).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub fn clear_resource_issues(db: State<'_, Database>) -> Result<(), String> {
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+    conn.execute("DELETE FROM resource_issues", []).map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+/// Reports file sizes and preset usage per model, grouped by type and architecture.
+#[tauri::command]
+pub async fn get_library_stats(db: State<'_, Database>) -> Result<LibraryStats, String> {
+    let (models, presets) = {
+        let conn = db.0.lock().map_err(|e| e.to_string())?;
+        (
+            load_all_models(&conn).map_err(|e| e.to_string())?,
+            load_all_presets(&conn).map_err(|e| e.to_string())?,
+        )
+    };
+
+    Ok(stats::library_stats(&models, &presets))
+}
+
+#[tauri::command]
+pub fn get_model_families(db: State<'_, Database>) -> Result<Vec<ModelFamily>, String> {
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+    families::load_families(&conn).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub fn create_model_family(db: State<'_, Database>, family: ModelFamily) -> Result<ModelFamily, String> {
+    let mut conn = db.0.lock().map_err(|e| e.to_string())?;
+    let now = Utc::now().to_rfc3339();
+    let family = ModelFamily {
+        id: Uuid::new_v4().to_string(),
+        created_at: now.clone(),
+        updated_at: now,
+        ..family
+    };
+
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+    let models = load_all_models(&tx).map_err(|e| e.to_string())?;
+    families::validate_family(&tx, &family, &models)?;
+    families::save_family(&tx, &family).map_err(|e| e.to_string())?;
+    tx.commit().map_err(|e| e.to_string())?;
+
+    Ok(family)
+}
+
+#[tauri::command]
+pub fn update_model_family(db: State<'_, Database>, family: ModelFamily) -> Result<ModelFamily, String> {
+    let mut conn = db.0.lock().map_err(|e| e.to_string())?;
+    let family = ModelFamily {
+        updated_at: Utc::now().to_rfc3339(),
+        ..family
+    };
+
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+    if families::load_family(&tx, &family.id).map_err(|e| e.to_string())?.is_none() {
+        return Err(format!("Model family not found: {}", family.id));
+    }
+    let models = load_all_models(&tx).map_err(|e| e.to_string())?;
+    families::validate_family(&tx, &family, &models)?;
+    families::save_family(&tx, &family).map_err(|e| e.to_string())?;
+    tx.commit().map_err(|e| e.to_string())?;
+
+    Ok(family)
+}
+
+#[tauri::command]
+pub fn delete_model_family(db: State<'_, Database>, id: String) -> Result<(), String> {
+    let mut conn = db.0.lock().map_err(|e| e.to_string())?;
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+    tx.execute("DELETE FROM model_family_versions WHERE family_id = ?1", params![id]).map_err(|e| e.to_string())?;
+    tx.execute("DELETE FROM model_families WHERE id = ?1", params![id]).map_err(|e| e.to_string())?;
+    tx.commit().map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+fn plan_family_upgrade(
+    conn: &Connection,
+    family_id: &str,
+    target_model_id: Option<&str>,
+) -> Result<Vec<PresetUpgrade>, String> {
+    let family = families::load_family(conn, family_id).map_err(|e| e.to_string())?
+        .ok_or_else(|| format!("Model family not found: {}", family_id))?;
+    let target = family.target(target_model_id)?;
+    let models = load_all_models(conn).map_err(|e| e.to_string())?;
+    let presets = load_all_presets(conn).map_err(|e| e.to_string())?;
+    Ok(families::plan_upgrades(&family, target, &presets, &models))
+}
+
+/// Lists the presets that use an older version of the family and what each
+/// reference would change to. Defaults to the newest version.
+#[tauri::command]
+pub fn preview_family_upgrade(db: State<'_, Database>, family_id: String, target_model_id: Option<String>) -> Result<Vec<PresetUpgrade>, String> {
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+    plan_family_upgrade(&conn, &family_id, target_model_id.as_deref())
+}
+
+/// Moves presets on older versions of the family to the target version.
+/// `preset_ids` limits the upgrade to some of the previewed presets.
+#[tauri::command]
+pub fn upgrade_family_presets(
+    db: State<'_, Database>,
+    family_id: String,
+    target_model_id: Option<String>,
+    preset_ids: Option<Vec<String>>,
+) -> Result<Vec<PresetSummary>, String> {
+    let mut conn = db.0.lock().map_err(|e| e.to_string())?;
+    let now = Utc::now().to_rfc3339();
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+
+    let upgrades = plan_family_upgrade(&tx, &family_id, target_model_id.as_deref())?;
+    let presets = load_all_presets(&tx).map_err(|e| e.to_string())?;
+
+    let mut touched = Vec::new();
+    for upgrade in upgrades {
+        if preset_ids.as_ref().is_some_and(|ids| !ids.contains(&upgrade.preset_id)) {
+            continue;
+        }
+        let Some(mut preset) = presets.iter().find(|p| p.id == upgrade.preset_id).cloned() else {
+            continue;
+        };
+        families::apply_upgrade(&mut preset.model, &upgrade.changes);
+        preset.updated_at = now.clone();
+        revisions::save_preset(&tx, &preset, &now).map_err(|e| e.to_string())?;
+        touched.push(PresetSummary {
+            id: preset.id,
+            name: preset.name,
+        });
+    }
+
+    tx.commit().map_err(|e| e.to_string())?;
+    Ok(touched)
+}
+
+/// Applies the same edits to many presets in one transaction. Each changed
+/// preset gets a revision, and presets inheriting from it follow. With
+/// `dry_run` nothing is saved, so the summary can be shown as a preview.
+#[tauri::command]
+pub fn bulk_update_presets(
+    db: State<'_, Database>,
+    ids: Vec<String>,
+    patch: Vec<PresetEdit>,
+    dry_run: Option<bool>,
+) -> Result<Vec<BulkUpdateSummary>, String> {
+    if patch.is_empty() {
+        return Err("No edits to apply".to_string());
+    }
+
+    let mut conn = db.0.lock().map_err(|e| e.to_string())?;
+    let now = Utc::now().to_rfc3339();
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+    let models = load_all_models(&tx).map_err(|e| e.to_string())?;
+
+    let mut summaries = Vec::new();
+    for id in ids {
+        let before = load_live_preset(&tx, &id)?;
+        let mut after = bulk::apply_edits(&before, &patch).map_err(|e| format!("{}: {}", before.name, e))?;
+        linking::link_preset(&mut after, &models, &mut RelinkReport::default());
+        resources::validate_resources(after.resources.as_ref())?;
+
+        let changes = diff::diff_presets(&before, &after)?;
+        if !changes.is_empty() {
+            after.updated_at = now.clone();
+            inheritance::prepare_child(&tx, &mut after)?;
+            revisions::save_preset(&tx, &after, &now).map_err(|e| e.to_string())?;
+            inheritance::refresh_children(&tx, &after, &now)?;
+        }
+        summaries.push(BulkUpdateSummary {
+            preset_id: id,
+            preset_name: after.name,
+            changes,
+        });
+    }
+
+    if !dry_run.unwrap_or(false) {
+        tx.commit().map_err(|e| e.to_string())?;
+    }
+    Ok(summaries)
+}
+
+/// Generates one variant of a preset per combination of axis values. Without
+/// `save` the variants are only returned; otherwise they are created as new
+/// presets, optionally inside a collection.
+#[tauri::command]
+pub fn generate_preset_sweep(
+    db: State<'_, Database>,
+    preset_id: String,
+    axes: Vec<SweepAxis>,
+    save: Option<SweepTarget>,
+) -> Result<PresetSweep, String> {
+    let mut conn = db.0.lock().map_err(|e| e.to_string())?;
+    let now = Utc::now().to_rfc3339();
+    let base = load_live_preset(&conn, &preset_id)?;
+    let models = load_all_models(&conn).map_err(|e| e.to_string())?;
+
+    let mut variants = sweep::generate(&base, &axes, &now)?;
+    for variant in &mut variants {
+        linking::link_preset(&mut variant.preset, &models, &mut RelinkReport::default());
+    }
+
+    let Some(target) = save else {
+        return Ok(PresetSweep { variants, collection_id: None });
+    };
+
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+    let collection_id = match target {
+        SweepTarget::Library => None,
+        SweepTarget::Collection { collection_id } => {
+            collections::require_collection(&tx, &collection_id)?;
+            Some(collection_id)
+        }
+        SweepTarget::NewCollection { name, parent_id } => {
+            let mut collection = Collection {
+                id: Uuid::new_v4().to_string(),
+                name,
+                description: String::new(),
+                parent_id,
+                position: 0,
+                preset_ids: Vec::new(),
+                created_at: now.clone(),
+                updated_at: now.clone(),
+            };
+            collections::insert_collection(&tx, &mut collection)?;
+            Some(collection.id)
+        }
+    };
+
+    for variant in &mut variants {
+        inheritance::prepare_child(&tx, &mut variant.preset)?;
+        insert_preset_row(&tx, &variant.preset).map_err(|e| e.to_string())?;
+        revisions::record_revision(&tx, &variant.preset, &now, None).map_err(|e| e.to_string())?;
+    }
+    if let Some(collection_id) = &collection_id {
+        let preset_ids: Vec<String> = variants.iter().map(|v| v.preset.id.clone()).collect();
+        collections::add_presets(&tx, collection_id, &preset_ids, None, &now)?;
+    }
+    tx.commit().map_err(|e| e.to_string())?;
+
+    Ok(PresetSweep { variants, collection_id })
+}
+
+#[tauri::command]
+pub fn get_collections(db: State<'_, Database>) -> Result<Vec<Collection>, String> {
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+    collections::load_collections(&conn).map_err(|e| e.to_string())
+}
+
+/// Creates a collection, placed last under its parent.
+#[tauri::command]
+pub fn create_collection(db: State<'_, Database>, collection: Collection) -> Result<Collection, String> {
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+    let now = Utc::now().to_rfc3339();
+    let mut collection = Collection {
+        id: Uuid::new_v4().to_string(),
+        created_at: now.clone(),
+        updated_at: now,
+        ..collection
+    };
+    collections::insert_collection(&conn, &mut collection)?;
+    Ok(collection)
+}
+
+/// Renames a collection or changes its description.
+#[tauri::command]
+pub fn update_collection(db: State<'_, Database>, collection: Collection) -> Result<Collection, String> {
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+    let collection = Collection {
+        updated_at: Utc::now().to_rfc3339(),
+        ..collection
+    };
+    collections::update_collection(&conn, &collection)?;
+    collections::require_collection(&conn, &collection.id)
+}
+
+/// Deletes a collection. Its sub-collections move up a level; its presets are
+/// not deleted.
+#[tauri::command]
+pub fn delete_collection(db: State<'_, Database>, id: String) -> Result<(), String> {
+    let mut conn = db.0.lock().map_err(|e| e.to_string())?;
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+    collections::delete_collection(&tx, &id, &Utc::now().to_rfc3339())?;
+    tx.commit().map_err(|e| e.to_string())
+}
+
+/// Moves a collection under another one, or to the top level without a
+/// parent, at `position` among its new siblings. Also reorders siblings.
+#[tauri::command]
+pub fn move_collection(db: State<'_, Database>, id: String, parent_id: Option<String>, position: Option<usize>) -> Result<Vec<Collection>, String> {
+    let mut conn = db.0.lock().map_err(|e| e.to_string())?;
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+    collections::move_collection(&tx, &id, parent_id.as_deref(), position, &Utc::now().to_rfc3339())?;
+    tx.commit().map_err(|e| e.to_string())?;
+    collections::load_collections(&conn).map_err(|e| e.to_string())
+}
+
+/// Adds presets to a collection at `position`, or at the end.
+#[tauri::command]
+pub fn add_presets_to_collection(db: State<'_, Database>, collection_id: String, preset_ids: Vec<String>, position: Option<usize>) -> Result<Collection, String> {
+    let mut conn = db.0.lock().map_err(|e| e.to_string())?;
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+    collections::add_presets(&tx, &collection_id, &preset_ids, position, &Utc::now().to_rfc3339())?;
+    tx.commit().map_err(|e| e.to_string())?;
+    collections::require_collection(&conn, &collection_id)
+}
+
+#[tauri::command]
+pub fn remove_presets_from_collection(db: State<'_, Database>, collection_id: String, preset_ids: Vec<String>) -> Result<Collection, String> {
+    let mut conn = db.0.lock().map_err(|e| e.to_string())?;
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+    collections::remove_presets(&tx, &collection_id, &preset_ids, &Utc::now().to_rfc3339())?;
+    tx.commit().map_err(|e| e.to_string())?;
+    collections::require_collection(&conn, &collection_id)
+}
+
+/// Moves presets from one collection to another in one step.
+#[tauri::command]
+pub fn move_presets_to_collection(
+    db: State<'_, Database>,
+    preset_ids: Vec<String>,
+    from_collection_id: String,
+    to_collection_id: String,
+    position: Option<usize>,
+) -> Result<Vec<Collection>, String> {
+    let mut conn = db.0.lock().map_err(|e| e.to_string())?;
+    let now = Utc::now().to_rfc3339();
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+    collections::remove_presets(&tx, &from_collection_id, &preset_ids, &now)?;
+    collections::add_presets(&tx, &to_collection_id, &preset_ids, position, &now)?;
+    tx.commit().map_err(|e| e.to_string())?;
+    collections::load_collections(&conn).map_err(|e| e.to_string())
+}
+
+/// Sets the manual order of a collection's presets.
+#[tauri::command]
+pub fn reorder_collection_presets(db: State<'_, Database>, collection_id: String, preset_ids: Vec<String>) -> Result<Collection, String> {
+    let mut conn = db.0.lock().map_err(|e| e.to_string())?;
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+    collections::reorder_presets(&tx, &collection_id, &preset_ids, &Utc::now().to_rfc3339())?;
+    tx.commit().map_err(|e| e.to_string())?;
+    collections::require_collection(&conn, &collection_id)
+}
+
+/// A collection with its presets and all sub-collections, for export.
+#[tauri::command]
+pub fn export_collection(db: State<'_, Database>, id: String) -> Result<CollectionExport, String> {
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+    collections::export_collection(&conn, &id)
+}
+
+#[tauri::command]
+pub fn get_preset_revisions(db: State<'_, Database>, preset_id: String) -> Result<Vec<PresetRevision>, String> {
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+    revisions::load_revisions(&conn, &preset_id).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub fn get_preset_revision(db: State<'_, Database>, revision_id: String) -> Result<PresetConfig, String> {
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+    revisions::load_snapshot(&conn, &revision_id).map_err(|e| e.to_string())?
+        .map(|(_, preset)| preset)
+        .ok_or_else(|| format!("Revision not found: {}", revision_id))
+}
+
+#[tauri::command]
+pub fn diff_preset_revisions(db: State<'_, Database>, from_revision_id: String, to_revision_id: String) -> Result<Vec<PresetChange>, String> {
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+    let load = |id: &str| revisions::load_snapshot(&conn, id).map_err(|e| e.to_string())?
+        .map(|(_, preset)| preset)
+        .ok_or_else(|| format!("Revision not found: {}", id));
+    let (from, to) = (load(&from_revision_id)?, load(&to_revision_id)?);
+    if from.id != to.id {
+        return Err("Revisions belong to different presets".to_string());
+    }
+    diff::diff_presets(&from, &to)
+}
+
+fn load_live_preset(conn: &Connection, id: &str) -> Result<PresetConfig, String> {
+    conn.query_row(
+        &format!("SELECT {} FROM presets WHERE id = ?1 AND deleted_at IS NULL", PRESET_COLUMNS),
+        params![id],
+        PresetConfig::from_row,
+    ).optional().map_err(|e| e.to_string())?
+        .ok_or_else(|| format!("Preset not found: {}", id))
+}
+
+/// Compares two presets, `before` first.
+#[tauri::command]
+pub fn diff_presets(db: State<'_, Database>, before_id: String, after_id: String) -> Result<Vec<PresetChange>, String> {
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+    let (before, after) = (load_live_preset(&conn, &before_id)?, load_live_preset(&conn, &after_id)?);
+    diff::diff_presets(&before, &after)
+}
+
+/// Three-way merges `theirs` into `ours` without saving. The base is the given
+/// revision or preset, or else the newest state both presets share in their
+/// revision history. Save the result with `update_preset`.
+#[tauri::command]
+pub fn merge_presets(
+    db: State<'_, Database>,
+    ours_id: String,
+    theirs_id: String,
+    base_revision_id: Option<String>,
+    base_preset_id: Option<String>,
+    resolutions: Option<HashMap<String, MergeSide>>,
+) -> Result<PresetMerge, String> {
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+    let ours = load_live_preset(&conn, &ours_id)?;
+    let theirs = load_live_preset(&conn, &theirs_id)?;
+
+    let base = match (base_revision_id, base_preset_id) {
+        (Some(revision_id), _) => revisions::load_snapshot(&conn, &revision_id).map_err(|e| e.to_string())?
+            .map(|(_, preset)| preset)
+            .ok_or_else(|| format!("Revision not found: {}", revision_id))?,
+        (None, Some(preset_id)) => load_live_preset(&conn, &preset_id)?,
+        (None, None) => merge::find_common_ancestor(&conn, &ours_id, &theirs_id)?
+            .ok_or("No common ancestor in the revision history; choose a base")?,
+    };
+
+    merge::merge_presets(&base, &ours, &theirs, &resolutions.unwrap_or_default())
+}
+
+/// Makes a revision the current state of its preset. The restore is recorded
+/// as a new revision, so it can itself be undone.
+#[tauri::command]
+pub fn restore_preset_revision(db: State<'_, Database>, revision_id: String) -> Result<PresetConfig, String> {
+    let mut conn = db.0.lock().map_err(|e| e.to_string())?;
+    let now = Utc::now().to_rfc3339();
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+
+    let (revision, snapshot) = revisions::load_snapshot(&tx, &revision_id).map_err(|e| e.to_string())?
+        .ok_or_else(|| format!("Revision not found: {}", revision_id))?;
+    let current = tx.query_row(
+        &format!("SELECT {} FROM presets WHERE id = ?1", PRESET_COLUMNS),
+        params![snapshot.id],
+        PresetConfig::from_row,
+    ).map_err(|e| e.to_string())?;
+
+    let mut preset = inheritance::resolve_from_parent(&tx, PresetConfig {
+        is_favorite: current.is_favorite,
+        use_count: current.use_count,
+        rating: current.rating,
+        notes: current.notes,
+        created_at: current.created_at,
+        updated_at: now.clone(),
+        ..snapshot
+    })?;
+    let models = load_all_models(&tx).map_err(|e| e.to_string())?;
+    linking::link_preset(&mut preset, &models, &mut RelinkReport::default());
+
+    update_preset_row(&tx, &preset).map_err(|e| e.to_string())?;
+    revisions::record_revision(&tx, &preset, &now, Some(revision)).map_err(|e| e.to_string())?;
+    inheritance::refresh_children(&tx, &preset, &now)?;
+    tx.commit().map_err(|e| e.to_string())?;
+
+    Ok(preset)
+}
+
+#[tauri::command]
+pub fn get_trash(db: State<'_, Database>) -> Result<Vec<TrashItem>, String> {
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+    trash::load_trash(&conn).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub fn restore_from_trash(db: State<'_, Database>, kind: TrashKind, id: String) -> Result<(), String> {
+    let mut conn = db.0.lock().map_err(|e| e.to_string())?;
+    if !trash::restore(&conn, kind, &id).map_err(|e| e.to_string())? {
+        return Err(format!("Not in the trash: {}", id));
+    }
+    if kind != TrashKind::Tag {
+        linking::relink_all_presets(&mut conn).map_err(|e| e.to_string())?;
+    }
+    Ok(())
+}
+
+/// Deletes trashed records for good, with their gallery files. Without
+/// `older_than_days` the whole trash is emptied.
+fn purge_trashed(app: &AppHandle, conn: &mut Connection, older_than_days: Option<u32>) -> Result<Vec<TrashItem>, String> {
+    let cutoff = older_than_days
+        .map(|days| (Utc::now() - chrono::Duration::days(days as i64)).to_rfc3339());
+
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+    let (purged, files) = trash::purge(&tx, cutoff.as_deref()).map_err(|e| e.to_string())?;
+    tx.commit().map_err(|e| e.to_string())?;
+
+    for image in &files.images {
+        let (_, thumbnail_dir) = gallery_dirs(app, &image.preset_id)?;
+        gallery::remove_image_files(image, &thumbnail_dir);
+    }
+    let (_, model_thumbnail_dir) = model_preview_dirs(app)?;
+    for (model_id, preview) in &files.model_previews {
+        if let Some(preview) = preview {
+            std::fs::remove_file(preview).ok();
+        }
+        thumbnails::remove_thumbnails(&model_thumbnail_dir, model_id);
+    }
+    Ok(purged)
+}
+
+/// Purges trash past its retention period. Runs in the background after
+/// startup; a failure is kept for the trash view to show.
+pub fn purge_expired_trash(app: &AppHandle) -> Result<(), String> {
+    let db = app.state::<Database>();
+    let mut conn = db.0.lock().map_err(|e| e.to_string())?;
+    let retention = trash::retention_days(&conn).unwrap_or(trash::DEFAULT_RETENTION_DAYS);
+    let error = purge_trashed(app, &mut conn, Some(retention)).err().unwrap_or_default();
+    set_setting(&conn, trash::PURGE_ERROR_SETTING, &error).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub fn purge_trash(app: AppHandle, db: State<'_, Database>, older_than_days: Option<u32>) -> Result<Vec<TrashItem>, String> {
+    let mut conn = db.0.lock().map_err(|e| e.to_string())?;
+    let purged = purge_trashed(&app, &mut conn, older_than_days)?;
+    set_setting(&conn, trash::PURGE_ERROR_SETTING, "").map_err(|e| e.to_string())?;
+    Ok(purged)
+}
+
+/// Why the last automatic purge failed, if it did.
+#[tauri::command]
+pub fn get_trash_purge_error(db: State<'_, Database>) -> Result<Option<String>, String> {
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+    let error = get_setting(&conn, trash::PURGE_ERROR_SETTING).map_err(|e| e.to_string())?;
+    Ok(error.filter(|e| !e.is_empty()))
+}
+
+#[tauri::command]
+pub fn get_trash_retention_days(db: State<'_, Database>) -> Result<u32, String> {
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+    trash::retention_days(&conn).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub fn set_trash_retention_days(db: State<'_, Database>, days: u32) -> Result<(), String> {
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+    set_setting(&conn, trash::RETENTION_SETTING, &days.to_string()).map_err(|e| e.to_string())
+}
@@ -1,11 +1,13 @@
-use rusqlite::{Connection, Result as SqliteResult};
+use rusqlite::types::{FromSql, FromSqlError, FromSqlResult, ToSqlOutput, ValueRef};
+use rusqlite::{params, Connection, OptionalExtension, Result as SqliteResult, Row, ToSql};
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 use std::sync::Mutex;
 use std::path::PathBuf;
 
 pub struct Database(pub Mutex<Connection>);
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct LoRA {
     pub name: String,
@@ -26,6 +28,11 @@ pub struct ModelConfig {
     pub refiner_model_id: Option<String>,
     pub refiner_switch: f64,
     pub loras: Vec<LoRA>,
+    /// VAE file name; empty means the checkpoint's built-in VAE.
+    #[serde(default)]
+    pub vae: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub vae_model_id: Option<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -54,15 +61,47 @@ pub struct ImageConfig {
     pub image_count: i32,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+/// A download link for one file. Reads both the plain URL strings Fooocus
+/// presets use and `{ "url": ..., "sha256": ... }` objects.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ResourceEntry {
+    pub url: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sha256: Option<String>,
+}
+
+impl<'de> Deserialize<'de> for ResourceEntry {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        #[derive(Deserialize)]
+        #[serde(untagged)]
+        enum Raw {
+            Url(String),
+            Entry {
+                url: String,
+                #[serde(default)]
+                sha256: Option<String>,
+            },
+        }
+
+        Ok(match Raw::deserialize(deserializer)? {
+            Raw::Url(url) => ResourceEntry { url, sha256: None },
+            Raw::Entry { url, sha256 } => ResourceEntry { url, sha256 },
+        })
+    }
+}
+
+pub type ResourceMap = BTreeMap<String, ResourceEntry>;
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct ResourceDownloads {
     #[serde(skip_serializing_if = "Option::is_none")]
-    pub checkpoint_downloads: Option<serde_json::Value>,
+    pub checkpoint_downloads: Option<ResourceMap>,
     #[serde(skip_serializing_if = "Option::is_none")]
-    pub lora_downloads: Option<serde_json::Value>,
+    pub lora_downloads: Option<ResourceMap>,
     #[serde(skip_serializing_if = "Option::is_none")]
-    pub embedding_downloads: Option<serde_json::Value>,
+    pub embedding_downloads: Option<ResourceMap>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -82,6 +121,21 @@ pub struct PresetConfig {
     pub image: ImageConfig,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub resources: Option<ResourceDownloads>,
+    /// Preset whose settings this one takes, except for the fields it overrides.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub parent_id: Option<String>,
+    /// Fields currently taken from the parent; empty without one.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub inherited_fields: Vec<String>,
+    /// Preset this one was duplicated from.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub source_preset_id: Option<String>,
+    /// 1 to 5, unset until the preset is rated.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rating: Option<u8>,
+    /// Free-form markdown.
+    #[serde(default)]
+    pub notes: String,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -93,6 +147,99 @@ pub struct Tag {
     pub count: i32,
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ModelType {
+    Checkpoint,
+    LoRA,
+    Refiner,
+    Embedding,
+    VAE,
+    Upscaler,
+    ControlNet,
+    IPAdapter,
+    Inpaint,
+}
+
+impl ModelType {
+    pub const ALL: [ModelType; 9] = [
+        ModelType::Checkpoint,
+        ModelType::LoRA,
+        ModelType::Refiner,
+        ModelType::Embedding,
+        ModelType::VAE,
+        ModelType::Upscaler,
+        ModelType::ControlNet,
+        ModelType::IPAdapter,
+        ModelType::Inpaint,
+    ];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            ModelType::Checkpoint => "Checkpoint",
+            ModelType::LoRA => "LoRA",
+            ModelType::Refiner => "Refiner",
+            ModelType::Embedding => "Embedding",
+            ModelType::VAE => "VAE",
+            ModelType::Upscaler => "Upscaler",
+            ModelType::ControlNet => "ControlNet",
+            ModelType::IPAdapter => "IP-Adapter",
+            ModelType::Inpaint => "Inpaint",
+        }
+    }
+}
+
+impl std::fmt::Display for ModelType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Parses case-insensitively and ignores separators, so `lora`, `ip_adapter`
+/// and `IP-Adapter` are all accepted.
+impl std::str::FromStr for ModelType {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let normalize = |s: &str| -> String {
+            s.chars()
+                .filter(|c| !matches!(c, '-' | '_' | ' '))
+                .flat_map(char::to_lowercase)
+                .collect()
+        };
+        let wanted = normalize(value);
+        ModelType::ALL.into_iter()
+            .find(|t| normalize(t.as_str()) == wanted)
+            .ok_or_else(|| format!("Unknown model type: {}", value))
+    }
+}
+
+impl Serialize for ModelType {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.as_str())
+    }
+}
+
+impl<'de> Deserialize<'de> for ModelType {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let value = String::deserialize(deserializer)?;
+        value.parse().map_err(serde::de::Error::custom)
+    }
+}
+
+impl ToSql for ModelType {
+    fn to_sql(&self) -> SqliteResult<ToSqlOutput<'_>> {
+        Ok(ToSqlOutput::from(self.as_str()))
+    }
+}
+
+impl FromSql for ModelType {
+    fn column_result(value: ValueRef<'_>) -> FromSqlResult<Self> {
+        value.as_str()?
+            .parse()
+            .map_err(|e: String| FromSqlError::Other(e.into()))
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct ModelInfo {
@@ -100,13 +247,25 @@ pub struct ModelInfo {
     pub name: String,
     pub file_name: String,
     #[serde(rename = "type")]
-    pub model_type: String,
+    pub model_type: ModelType,
     pub description: String,
     pub scope: Vec<String>,
     pub path: String,
     pub tags: Vec<String>,
     pub created_at: String,
     pub updated_at: String,
+    #[serde(default)]
+    pub trigger_words: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub recommended_weight_min: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub recommended_weight_max: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub recommended_base_model: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub source_url: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub preview_image: Option<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -117,15 +276,464 @@ pub struct ModelUsageInfo {
     pub preset_names: Vec<String>,
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PresetSummary {
+    pub id: String,
+    pub name: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ModelUpdateResult {
+    pub model: ModelInfo,
+    pub touched_presets: Vec<PresetSummary>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ModelDeletionResult {
+    pub undo_id: String,
+    pub touched_presets: Vec<PresetSummary>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ModelMergeResult {
+    pub removed_model_ids: Vec<String>,
+    pub touched_presets: Vec<PresetSummary>,
+}
+
+pub const PRESET_COLUMNS: &str = "id, name, description, tags, is_favorite, use_count, created_at, updated_at,
+                model_config, sampling_config, prompt_config, image_config, resources, parent_id, overrides, source_preset_id, rating, notes";
+
+pub const MODEL_COLUMNS: &str = "id, name, file_name, model_type, description, scope, path, tags, created_at, updated_at,
+                trigger_words, recommended_weight_min, recommended_weight_max, recommended_base_model, source_url, preview_image";
+
+impl PresetConfig {
+    pub fn from_row(row: &Row) -> SqliteResult<Self> {
+        let mut preset = PresetConfig {
+            id: row.get(0)?,
+            name: row.get(1)?,
+            description: row.get(2)?,
+            tags: serde_json::from_str(&row.get::<_, String>(3)?).unwrap_or_default(),
+            is_favorite: row.get::<_, i32>(4)? != 0,
+            use_count: row.get(5)?,
+            created_at: row.get(6)?,
+            updated_at: row.get(7)?,
+            model: serde_json::from_str(&row.get::<_, String>(8)?).unwrap_or_else(|_| ModelConfig {
+                base_model: String::new(),
+                base_model_id: None,
+                refiner_model: String::new(),
+                refiner_model_id: None,
+                refiner_switch: 0.5,
+                loras: vec![],
+                vae: String::new(),
+                vae_model_id: None,
+            }),
+            sampling: serde_json::from_str(&row.get::<_, String>(9)?).unwrap_or_else(|_| SamplingConfig {
+                cfg_scale: 7.0,
+                sample_sharpness: 2.0,
+                sampler: String::from("dpmpp_2m_sde_gpu"),
+                scheduler: String::from("karras"),
+                performance: String::from("Speed"),
+                steps: 30,
+            }),
+            prompt: serde_json::from_str(&row.get::<_, String>(10)?).unwrap_or_else(|_| PromptConfig {
+                positive: String::new(),
+                negative: String::new(),
+                styles: vec![],
+            }),
+            image: serde_json::from_str(&row.get::<_, String>(11)?).unwrap_or_else(|_| ImageConfig {
+                aspect_ratio: String::from("1152*896"),
+                image_count: 4,
+            }),
+            resources: row.get::<_, Option<String>>(12)?.and_then(|s| serde_json::from_str(&s).ok()),
+            parent_id: row.get(13)?,
+            inherited_fields: vec![],
+            source_preset_id: row.get(15)?,
+            rating: row.get(16)?,
+            notes: row.get::<_, Option<String>>(17)?.unwrap_or_default(),
+        };
+        let overrides: Vec<String> = serde_json::from_str(&row.get::<_, String>(14)?).unwrap_or_default();
+        preset.inherited_fields = crate::inheritance::inherited_fields(&preset, &overrides);
+        Ok(preset)
+    }
+}
+
+impl ModelConfig {
+    pub fn references_model(&self, model_id: &str) -> bool {
+        self.base_model_id.as_deref() == Some(model_id)
+            || self.refiner_model_id.as_deref() == Some(model_id)
+            || self.vae_model_id.as_deref() == Some(model_id)
+            || self.loras.iter().any(|l| l.model_id.as_deref() == Some(model_id))
+    }
+}
+
+impl ModelInfo {
+    pub fn from_row(row: &Row) -> SqliteResult<Self> {
+        Ok(ModelInfo {
+            id: row.get(0)?,
+            name: row.get(1)?,
+            file_name: row.get(2)?,
+            model_type: row.get(3)?,
+            description: row.get(4)?,
+            scope: serde_json::from_str(&row.get::<_, String>(5)?).unwrap_or_default(),
+            path: row.get(6)?,
+            tags: serde_json::from_str(&row.get::<_, String>(7)?).unwrap_or_default(),
+            created_at: row.get(8)?,
+            updated_at: row.get(9)?,
+            trigger_words: row.get::<_, Option<String>>(10)?
+                .and_then(|s| serde_json::from_str(&s).ok())
+                .unwrap_or_default(),
+            recommended_weight_min: row.get(11)?,
+            recommended_weight_max: row.get(12)?,
+            recommended_base_model: row.get(13)?,
+            source_url: row.get(14)?,
+            preview_image: row.get(15)?,
+        })
+    }
+}
+
+pub fn load_all_presets(conn: &Connection) -> SqliteResult<Vec<PresetConfig>> {
+    let mut stmt = conn.prepare(&format!(
+        "SELECT {} FROM presets WHERE deleted_at IS NULL ORDER BY updated_at DESC",
+        PRESET_COLUMNS
+    ))?;
+    let presets = stmt.query_map([], PresetConfig::from_row)?;
+    presets.collect()
+}
+
+pub fn load_all_models(conn: &Connection) -> SqliteResult<Vec<ModelInfo>> {
+    let mut stmt = conn.prepare(&format!(
+        "SELECT {} FROM models WHERE deleted_at IS NULL ORDER BY updated_at DESC",
+        MODEL_COLUMNS
+    ))?;
+    let models = stmt.query_map([], ModelInfo::from_row)?;
+    models.collect()
+}
+
+pub fn insert_model(conn: &Connection, model: &ModelInfo) -> SqliteResult<()> {
+    let scope_json = serde_json::to_string(&model.scope)
+        .map_err(|e| rusqlite::Error::ToSqlConversionFailure(Box::new(e)))?;
+    let tags_json = serde_json::to_string(&model.tags)
+        .map_err(|e| rusqlite::Error::ToSqlConversionFailure(Box::new(e)))?;
+    let trigger_words_json = serde_json::to_string(&model.trigger_words)
+        .map_err(|e| rusqlite::Error::ToSqlConversionFailure(Box::new(e)))?;
+
+    conn.execute(
+        "INSERT INTO models (id, name, file_name, model_type, description, scope, path, tags, created_at, updated_at,
+         trigger_words, recommended_weight_min, recommended_weight_max, recommended_base_model, source_url, preview_image)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16)",
+        params![
+            model.id,
+            model.name,
+            model.file_name,
+            model.model_type,
+            model.description,
+            scope_json,
+            model.path,
+            tags_json,
+            model.created_at,
+            model.updated_at,
+            trigger_words_json,
+            model.recommended_weight_min,
+            model.recommended_weight_max,
+            model.recommended_base_model,
+            model.source_url,
+            model.preview_image,
+        ],
+    )?;
+    Ok(())
+}
+
+pub fn update_model_row(conn: &Connection, model: &ModelInfo) -> SqliteResult<()> {
+    let scope_json = serde_json::to_string(&model.scope)
+        .map_err(|e| rusqlite::Error::ToSqlConversionFailure(Box::new(e)))?;
+    let tags_json = serde_json::to_string(&model.tags)
+        .map_err(|e| rusqlite::Error::ToSqlConversionFailure(Box::new(e)))?;
+    let trigger_words_json = serde_json::to_string(&model.trigger_words)
+        .map_err(|e| rusqlite::Error::ToSqlConversionFailure(Box::new(e)))?;
+
+    conn.execute(
+        "UPDATE models SET name = ?1, file_name = ?2, model_type = ?3, description = ?4, 
+         scope = ?5, path = ?6, tags = ?7, updated_at = ?8, trigger_words = ?9, 
+         recommended_weight_min = ?10, recommended_weight_max = ?11, recommended_base_model = ?12, 
+         source_url = ?13, preview_image = ?14 WHERE id = ?15",
+        params![
+            model.name,
+            model.file_name,
+            model.model_type,
+            model.description,
+            scope_json,
+            model.path,
+            tags_json,
+            model.updated_at,
+            trigger_words_json,
+            model.recommended_weight_min,
+            model.recommended_weight_max,
+            model.recommended_base_model,
+            model.source_url,
+            model.preview_image,
+            model.id,
+        ],
+    )?;
+    Ok(())
+}
+
+pub fn insert_preset_row(conn: &Connection, preset: &PresetConfig) -> SqliteResult<()> {
+    let to_sql_error = |e: serde_json::Error| rusqlite::Error::ToSqlConversionFailure(Box::new(e));
+    let resources_json = preset.resources.as_ref()
+        .map(serde_json::to_string)
+        .transpose()
+        .map_err(to_sql_error)?;
+
+    conn.execute(
+        "INSERT INTO presets (id, name, description, tags, is_favorite, use_count, created_at, updated_at, 
+         model_config, sampling_config, prompt_config, image_config, resources, parent_id, overrides, source_preset_id, rating, notes)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16, ?17, ?18)",
+        params![
+            preset.id,
+            preset.name,
+            preset.description,
+            serde_json::to_string(&preset.tags).map_err(to_sql_error)?,
+            preset.is_favorite as i32,
+            preset.use_count,
+            preset.created_at,
+            preset.updated_at,
+            serde_json::to_string(&preset.model).map_err(to_sql_error)?,
+            serde_json::to_string(&preset.sampling).map_err(to_sql_error)?,
+            serde_json::to_string(&preset.prompt).map_err(to_sql_error)?,
+            serde_json::to_string(&preset.image).map_err(to_sql_error)?,
+            resources_json,
+            preset.parent_id,
+            serde_json::to_string(&crate::inheritance::overrides(preset)).map_err(to_sql_error)?,
+            preset.source_preset_id,
+            preset.rating,
+            preset.notes,
+        ],
+    )?;
+    Ok(())
+}
+
+pub fn update_preset_row(conn: &Connection, preset: &PresetConfig) -> SqliteResult<()> {
+    let to_sql_error = |e: serde_json::Error| rusqlite::Error::ToSqlConversionFailure(Box::new(e));
+    let tags_json = serde_json::to_string(&preset.tags).map_err(to_sql_error)?;
+    let model_json = serde_json::to_string(&preset.model).map_err(to_sql_error)?;
+    let sampling_json = serde_json::to_string(&preset.sampling).map_err(to_sql_error)?;
+    let prompt_json = serde_json::to_string(&preset.prompt).map_err(to_sql_error)?;
+    let image_json = serde_json::to_string(&preset.image).map_err(to_sql_error)?;
+    let resources_json = preset.resources.as_ref()
+        .map(serde_json::to_string)
+        .transpose()
+        .map_err(to_sql_error)?;
+    let overrides_json = serde_json::to_string(&crate::inheritance::overrides(preset)).map_err(to_sql_error)?;
+
+    conn.execute(
+        "UPDATE presets SET name = ?1, description = ?2, tags = ?3, is_favorite = ?4, 
+         updated_at = ?5, model_config = ?6, sampling_config = ?7, prompt_config = ?8, 
+         image_config = ?9, resources = ?10, parent_id = ?12, overrides = ?13, rating = ?14, notes = ?15 WHERE id = ?11",
+        params![
+            preset.name,
+            preset.description,
+            tags_json,
+            preset.is_favorite as i32,
+            preset.updated_at,
+            model_json,
+            sampling_json,
+            prompt_json,
+            image_json,
+            resources_json,
+            preset.id,
+            preset.parent_id,
+            overrides_json,
+            preset.rating,
+            preset.notes,
+        ],
+    )?;
+    Ok(())
+}
+
+pub fn get_setting(conn: &Connection, key: &str) -> SqliteResult<Option<String>> {
+    conn.query_row(
+        "SELECT value FROM settings WHERE key = ?1",
+        params![key],
+        |row| row.get(0),
+    ).optional()
+}
+
+pub fn set_setting(conn: &Connection, key: &str, value: &str) -> SqliteResult<()> {
+    conn.execute(
+        "INSERT OR REPLACE INTO settings (key, value) VALUES (?1, ?2)",
+        params![key, value],
+    )?;
+    Ok(())
+}
+
+fn add_column_if_missing(conn: &Connection, table: &str, column: &str, definition: &str) -> SqliteResult<()> {
+    let mut stmt = conn.prepare(&format!("PRAGMA table_info({})", table))?;
+    let columns = stmt.query_map([], |row| row.get::<_, String>(1))?
+        .collect::<SqliteResult<Vec<_>>>()?;
+    if !columns.iter().any(|c| c == column) {
+        conn.execute(&format!("ALTER TABLE {} ADD COLUMN {} {}", table, column, definition), [])?;
+    }
+    Ok(())
+}
+
+/// An empty in-memory database with the full schema.
+#[cfg(test)]
+pub fn open_in_memory() -> SqliteResult<Connection> {
+    let conn = Connection::open_in_memory()?;
+    Database::init_tables(&conn)?;
+    Database::migrate_tables(&conn)?;
+    Ok(conn)
+}
+
+/// A preset with default settings and no model references.
+#[cfg(test)]
+pub fn test_preset(id: &str) -> PresetConfig {
+    PresetConfig {
+        id: id.to_string(),
+        name: id.to_string(),
+        description: String::new(),
+        tags: vec![],
+        is_favorite: false,
+        use_count: 0,
+        created_at: "2024-01-01T00:00:00+00:00".to_string(),
+        updated_at: "2024-01-01T00:00:00+00:00".to_string(),
+        model: ModelConfig {
+            base_model: String::new(),
+            base_model_id: None,
+            refiner_model: String::new(),
+            refiner_model_id: None,
+            refiner_switch: 0.5,
+            loras: vec![],
+            vae: String::new(),
+            vae_model_id: None,
+        },
+        sampling: SamplingConfig {
+            cfg_scale: 7.0,
+            sample_sharpness: 2.0,
+            sampler: "dpmpp_2m_sde_gpu".to_string(),
+            scheduler: "karras".to_string(),
+            performance: "Speed".to_string(),
+            steps: 30,
+        },
+        prompt: PromptConfig {
+            positive: String::new(),
+            negative: String::new(),
+            styles: vec![],
+        },
+        image: ImageConfig {
+            aspect_ratio: "1152*896".to_string(),
+            image_count: 4,
+        },
+        resources: None,
+        parent_id: None,
+        inherited_fields: vec![],
+        source_preset_id: None,
+        rating: None,
+        notes: String::new(),
+    }
+}
+
 impl Database {
     pub fn new(app_data_dir: PathBuf) -> SqliteResult<Self> {
         std::fs::create_dir_all(&app_data_dir).ok();
         let db_path = app_data_dir.join("fooocus_config.db");
         let conn = Connection::open(db_path)?;
         Self::init_tables(&conn)?;
+        Self::migrate_tables(&conn)?;
         Ok(Database(Mutex::new(conn)))
     }
 
+    fn migrate_tables(conn: &Connection) -> SqliteResult<()> {
+        add_column_if_missing(conn, "models", "trigger_words", "TEXT")?;
+        add_column_if_missing(conn, "models", "recommended_weight_min", "REAL")?;
+        add_column_if_missing(conn, "models", "recommended_weight_max", "REAL")?;
+        add_column_if_missing(conn, "models", "recommended_base_model", "TEXT")?;
+        add_column_if_missing(conn, "models", "source_url", "TEXT")?;
+        add_column_if_missing(conn, "models", "preview_image", "TEXT")?;
+        add_column_if_missing(conn, "models", "deleted_at", "TEXT")?;
+        add_column_if_missing(conn, "presets", "deleted_at", "TEXT")?;
+        add_column_if_missing(conn, "tags", "deleted_at", "TEXT")?;
+        add_column_if_missing(conn, "presets", "parent_id", "TEXT")?;
+        add_column_if_missing(conn, "presets", "overrides", "TEXT NOT NULL DEFAULT '[]'")?;
+        conn.execute("CREATE INDEX IF NOT EXISTS idx_presets_parent ON presets(parent_id)", [])?;
+        add_column_if_missing(conn, "presets", "source_preset_id", "TEXT")?;
+        add_column_if_missing(conn, "presets", "rating", "INTEGER")?;
+        add_column_if_missing(conn, "presets", "notes", "TEXT")?;
+        crate::resources::migrate_resource_rows(conn)?;
+        Self::normalize_model_types(conn)?;
+        crate::revisions::backfill_baselines(conn)?;
+        Self::detach_orphaned_children(conn)?;
+        Ok(())
+    }
+
+    /// Detaches children of presets trashed before trashing detached them.
+    fn detach_orphaned_children(conn: &Connection) -> SqliteResult<()> {
+        let orphans: Vec<PresetConfig> = {
+            let mut stmt = conn.prepare(&format!(
+                "SELECT {} FROM presets WHERE parent_id IN (SELECT id FROM presets WHERE deleted_at IS NOT NULL)",
+                PRESET_COLUMNS
+            ))?;
+            let rows = stmt.query_map([], PresetConfig::from_row)?;
+            rows.collect::<SqliteResult<_>>()?
+        };
+
+        let now = chrono::Utc::now().to_rfc3339();
+        for orphan in orphans {
+            let orphan = PresetConfig {
+                parent_id: None,
+                inherited_fields: vec![],
+                updated_at: now.clone(),
+                ..orphan
+            };
+            crate::revisions::save_preset(conn, &orphan, &now)?;
+        }
+        Ok(())
+    }
+
+    /// Rewrites free-form model types to their canonical names. Types that match
+    /// no known kind become `Checkpoint`, keeping the old value as a tag.
+    fn normalize_model_types(conn: &Connection) -> SqliteResult<()> {
+        let stored: Vec<String> = {
+            let mut stmt = conn.prepare("SELECT DISTINCT model_type FROM models")?;
+            let rows = stmt.query_map([], |row| row.get(0))?;
+            rows.collect::<SqliteResult<_>>()?
+        };
+
+        for value in stored {
+            match value.parse::<ModelType>() {
+                Ok(model_type) if model_type.as_str() == value => {}
+                Ok(model_type) => {
+                    conn.execute(
+                        "UPDATE models SET model_type = ?1 WHERE model_type = ?2",
+                        params![model_type, value],
+                    )?;
+                }
+                Err(_) => {
+                    let ids: Vec<(String, Option<String>)> = {
+                        let mut stmt = conn.prepare("SELECT id, tags FROM models WHERE model_type = ?1")?;
+                        let rows = stmt.query_map(params![value], |row| Ok((row.get(0)?, row.get(1)?)))?;
+                        rows.collect::<SqliteResult<_>>()?
+                    };
+                    for (id, tags) in ids {
+                        let mut tags: Vec<String> = tags.and_then(|t| serde_json::from_str(&t).ok()).unwrap_or_default();
+                        tags.push(format!("type:{}", value));
+                        let tags_json = serde_json::to_string(&tags)
+                            .map_err(|e| rusqlite::Error::ToSqlConversionFailure(Box::new(e)))?;
+                        conn.execute(
+                            "UPDATE models SET model_type = ?1, tags = ?2 WHERE id = ?3",
+                            params![ModelType::Checkpoint, tags_json, id],
+                        )?;
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
     fn init_tables(conn: &Connection) -> SqliteResult<()> {
         conn.execute_batch(
             r#"
@@ -164,11 +772,108 @@ impl Database {
                 updated_at TEXT
             );
 
+            CREATE TABLE IF NOT EXISTS undo_records (
+                id TEXT PRIMARY KEY,
+                action TEXT NOT NULL,
+                payload TEXT NOT NULL,
+                created_at TEXT
+            );
+
+            CREATE TABLE IF NOT EXISTS file_hashes (
+                path TEXT PRIMARY KEY,
+                size INTEGER NOT NULL,
+                modified INTEGER NOT NULL,
+                hash TEXT NOT NULL
+            );
+
+            CREATE TABLE IF NOT EXISTS settings (
+                key TEXT PRIMARY KEY,
+                value TEXT NOT NULL
+            );
+
+            CREATE TABLE IF NOT EXISTS resource_issues (
+                preset_id TEXT NOT NULL,
+                preset_name TEXT,
+                kind TEXT NOT NULL,
+                file_name TEXT,
+                value TEXT,
+                reason TEXT
+            );
+
+            CREATE TABLE IF NOT EXISTS preset_images (
+                id TEXT PRIMARY KEY,
+                preset_id TEXT NOT NULL,
+                path TEXT NOT NULL,
+                is_copy INTEGER DEFAULT 0,
+                is_cover INTEGER DEFAULT 0,
+                notes TEXT,
+                rating INTEGER,
+                sort_order INTEGER DEFAULT 0,
+                created_at TEXT
+            );
+
+            CREATE TABLE IF NOT EXISTS preset_revisions (
+                id TEXT PRIMARY KEY,
+                preset_id TEXT NOT NULL,
+                revision INTEGER NOT NULL,
+                snapshot TEXT NOT NULL,
+                changed_fields TEXT,
+                restored_from INTEGER,
+                created_at TEXT
+            );
+
+            CREATE TABLE IF NOT EXISTS model_families (
+                id TEXT PRIMARY KEY,
+                name TEXT NOT NULL,
+                description TEXT,
+                created_at TEXT,
+                updated_at TEXT
+            );
+
+            CREATE TABLE IF NOT EXISTS model_family_versions (
+                family_id TEXT NOT NULL,
+                model_id TEXT NOT NULL UNIQUE,
+                label TEXT NOT NULL,
+                position INTEGER NOT NULL
+            );
+
+            CREATE TABLE IF NOT EXISTS collections (
+                id TEXT PRIMARY KEY,
+                name TEXT NOT NULL,
+                description TEXT,
+                parent_id TEXT,
+                position INTEGER NOT NULL DEFAULT 0,
+                created_at TEXT,
+                updated_at TEXT
+            );
+
+            CREATE TABLE IF NOT EXISTS collection_presets (
+                collection_id TEXT NOT NULL,
+                preset_id TEXT NOT NULL,
+                position INTEGER NOT NULL,
+                PRIMARY KEY (collection_id, preset_id)
+            );
+
+            CREATE TABLE IF NOT EXISTS preset_outcomes (
+                id TEXT PRIMARY KEY,
+                preset_id TEXT NOT NULL,
+                outcome TEXT NOT NULL,
+                comment TEXT,
+                used_at TEXT NOT NULL
+            );
+
             CREATE INDEX IF NOT EXISTS idx_presets_name ON presets(name);
             CREATE INDEX IF NOT EXISTS idx_presets_created_at ON presets(created_at);
             CREATE INDEX IF NOT EXISTS idx_presets_is_favorite ON presets(is_favorite);
             CREATE INDEX IF NOT EXISTS idx_models_name ON models(name);
             CREATE INDEX IF NOT EXISTS idx_models_type ON models(model_type);
+            CREATE INDEX IF NOT EXISTS idx_file_hashes_hash ON file_hashes(hash);
+            CREATE INDEX IF NOT EXISTS idx_preset_images_preset ON preset_images(preset_id);
+            CREATE INDEX IF NOT EXISTS idx_preset_revisions_preset ON preset_revisions(preset_id, revision);
+            CREATE INDEX IF NOT EXISTS idx_model_family_versions_family ON model_family_versions(family_id);
+            CREATE INDEX IF NOT EXISTS idx_collections_parent ON collections(parent_id, position);
+            CREATE INDEX IF NOT EXISTS idx_collection_presets_preset ON collection_presets(preset_id);
+            CREATE INDEX IF NOT EXISTS idx_preset_outcomes_preset ON preset_outcomes(preset_id, used_at);
             "#,
         )?;
         Ok(())
@@ -0,0 +1,282 @@
+use crate::database::{LoRA, PresetConfig};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+/// Bookkeeping fields that change without the preset's settings changing.
+const IGNORED_FIELDS: &[&str] = &["id", "createdAt", "updatedAt", "useCount", "isFavorite", "inheritedFields", "sourcePresetId", "rating", "notes"];
+
+/// Fields with a dedicated comparison; the generic one skips them.
+const SPECIAL_FIELDS: &[(&str, &str)] = &[
+    ("model", "loras"),
+    ("prompt", "positive"),
+    ("prompt", "negative"),
+    ("prompt", "styles"),
+];
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FieldChange {
+    /// Dotted path such as `sampling.steps` or `resources.loraDownloads.x.url`.
+    pub path: String,
+    pub before: Value,
+    pub after: Value,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum WordOp {
+    Equal,
+    Insert,
+    Delete,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WordSpan {
+    pub op: WordOp,
+    pub text: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "camelCase", rename_all_fields = "camelCase")]
+pub enum PresetChange {
+    Field(FieldChange),
+    LoraAdded {
+        lora: LoRA,
+    },
+    LoraRemoved {
+        lora: LoRA,
+    },
+    LoraReweighted {
+        lora: LoRA,
+        before: f64,
+        after: f64,
+    },
+    /// Order-insensitive lists such as styles and tags.
+    SetChanged {
+        path: String,
+        added: Vec<String>,
+        removed: Vec<String>,
+    },
+    PromptChanged {
+        negative: bool,
+        spans: Vec<WordSpan>,
+    },
+}
+
+impl PresetChange {
+    /// The field the change belongs to, for revision summaries.
+    pub fn path(&self) -> &str {
+        match self {
+            PresetChange::Field(change) => &change.path,
+            PresetChange::LoraAdded { .. }
+            | PresetChange::LoraRemoved { .. }
+            | PresetChange::LoraReweighted { .. } => "model.loras",
+            PresetChange::SetChanged { path, .. } => path,
+            PresetChange::PromptChanged { negative: false, .. } => "prompt.positive",
+            PresetChange::PromptChanged { negative: true, .. } => "prompt.negative",
+        }
+    }
+}
+
+fn child_path(parent: &str, key: &str) -> String {
+    if parent.is_empty() {
+        key.to_string()
+    } else {
+        format!("{}.{}", parent, key)
+    }
+}
+
+fn diff_into(path: &str, before: &Value, after: &Value, changes: &mut Vec<FieldChange>) {
+    match (before, after) {
+        (Value::Object(a), Value::Object(b)) => {
+            let mut keys: Vec<&String> = a.keys().chain(b.keys().filter(|k| !a.contains_key(*k))).collect();
+            keys.sort();
+            for key in keys {
+                let (x, y) = (a.get(key).unwrap_or(&Value::Null), b.get(key).unwrap_or(&Value::Null));
+                diff_into(&child_path(path, key), x, y, changes);
+            }
+        }
+        (Value::Array(a), Value::Array(b)) if a.iter().chain(b).all(Value::is_object) => {
+            for index in 0..a.len().max(b.len()) {
+                let (x, y) = (a.get(index).unwrap_or(&Value::Null), b.get(index).unwrap_or(&Value::Null));
+                diff_into(&format!("{}[{}]", path, index), x, y, changes);
+            }
+        }
+        _ if before != after => changes.push(FieldChange {
+            path: path.to_string(),
+            before: before.clone(),
+            after: after.clone(),
+        }),
+        _ => {}
+    }
+}
+
+/// Field-level differences between two JSON documents. Objects and lists of
+/// objects are compared member by member; anything else is one value.
+pub fn diff_values(before: &Value, after: &Value) -> Vec<FieldChange> {
+    let mut changes = Vec::new();
+    diff_into("", before, after, &mut changes);
+    changes
+}
+
+/// Splits text into runs of word characters, runs of whitespace and single
+/// punctuation marks, so joining the tokens gives back the text.
+fn tokenize(text: &str) -> Vec<&str> {
+    #[derive(PartialEq)]
+    enum Class {
+        Word,
+        Space,
+        Mark,
+    }
+    let class = |c: char| {
+        if c.is_alphanumeric() || c == '_' {
+            Class::Word
+        } else if c.is_whitespace() {
+            Class::Space
+        } else {
+            Class::Mark
+        }
+    };
+
+    let mut tokens = Vec::new();
+    let mut start = 0;
+    let mut previous: Option<Class> = None;
+    for (index, c) in text.char_indices() {
+        let current = class(c);
+        let continues = previous.as_ref().is_some_and(|p| *p == current && current != Class::Mark);
+        if !continues && index > start {
+            tokens.push(&text[start..index]);
+            start = index;
+        }
+        previous = Some(current);
+    }
+    if start < text.len() {
+        tokens.push(&text[start..]);
+    }
+    tokens
+}
+
+fn push_span(spans: &mut Vec<WordSpan>, op: WordOp, text: &str) {
+    match spans.last_mut() {
+        Some(last) if last.op == op => last.text.push_str(text),
+        _ => spans.push(WordSpan { op, text: text.to_string() }),
+    }
+}
+
+/// Word-level diff from the longest common subsequence of the two token lists.
+pub fn diff_words(before: &str, after: &str) -> Vec<WordSpan> {
+    let (a, b) = (tokenize(before), tokenize(after));
+    let (n, m) = (a.len(), b.len());
+
+    let mut lengths = vec![vec![0u32; m + 1]; n + 1];
+    for i in (0..n).rev() {
+        for j in (0..m).rev() {
+            lengths[i][j] = if a[i] == b[j] {
+                lengths[i + 1][j + 1] + 1
+            } else {
+                lengths[i + 1][j].max(lengths[i][j + 1])
+            };
+        }
+    }
+
+    let mut spans = Vec::new();
+    let (mut i, mut j) = (0, 0);
+    while i < n || j < m {
+        if i < n && j < m && a[i] == b[j] {
+            push_span(&mut spans, WordOp::Equal, a[i]);
+            i += 1;
+            j += 1;
+        } else if j < m && (i == n || lengths[i][j + 1] >= lengths[i + 1][j]) {
+            push_span(&mut spans, WordOp::Insert, b[j]);
+            j += 1;
+        } else {
+            push_span(&mut spans, WordOp::Delete, a[i]);
+            i += 1;
+        }
+    }
+    spans
+}
+
+fn lora_key(lora: &LoRA) -> String {
+    match &lora.model_id {
+        Some(id) => id.clone(),
+        None => lora.model_name.to_lowercase(),
+    }
+}
+
+fn diff_loras(before: &[LoRA], after: &[LoRA], changes: &mut Vec<PresetChange>) {
+    for old in before {
+        match after.iter().find(|l| lora_key(l) == lora_key(old)) {
+            None => changes.push(PresetChange::LoraRemoved { lora: old.clone() }),
+            Some(new) if new.weight != old.weight => changes.push(PresetChange::LoraReweighted {
+                lora: new.clone(),
+                before: old.weight,
+                after: new.weight,
+            }),
+            Some(_) => {}
+        }
+    }
+    for new in after {
+        if !before.iter().any(|l| lora_key(l) == lora_key(new)) {
+            changes.push(PresetChange::LoraAdded { lora: new.clone() });
+        }
+    }
+}
+
+fn diff_set(path: &str, before: &[String], after: &[String], changes: &mut Vec<PresetChange>) {
+    let added: Vec<String> = after.iter().filter(|s| !before.contains(s)).cloned().collect();
+    let removed: Vec<String> = before.iter().filter(|s| !after.contains(s)).cloned().collect();
+    if !added.is_empty() || !removed.is_empty() {
+        changes.push(PresetChange::SetChanged {
+            path: path.to_string(),
+            added,
+            removed,
+        });
+    }
+}
+
+fn diff_prompt(negative: bool, before: &str, after: &str, changes: &mut Vec<PresetChange>) {
+    if before != after {
+        changes.push(PresetChange::PromptChanged {
+            negative,
+            spans: diff_words(before, after),
+        });
+    }
+}
+
+/// A preset as JSON without its bookkeeping fields.
+pub fn settings_value(preset: &PresetConfig) -> Result<Value, String> {
+    let mut value = serde_json::to_value(preset).map_err(|e| e.to_string())?;
+    if let Some(object) = value.as_object_mut() {
+        object.retain(|key, _| !IGNORED_FIELDS.contains(&key.as_str()));
+    }
+    Ok(value)
+}
+
+/// Everything that differs between two presets, ignoring bookkeeping fields.
+pub fn diff_presets(before: &PresetConfig, after: &PresetConfig) -> Result<Vec<PresetChange>, String> {
+    let to_value = |preset: &PresetConfig| -> Result<Value, String> {
+        let mut value = settings_value(preset)?;
+        if let Some(object) = value.as_object_mut() {
+            object.remove("tags");
+            for (section, field) in SPECIAL_FIELDS {
+                if let Some(section) = object.get_mut(*section).and_then(Value::as_object_mut) {
+                    section.remove(*field);
+                }
+            }
+        }
+        Ok(value)
+    };
+
+    let mut changes: Vec<PresetChange> = diff_values(&to_value(before)?, &to_value(after)?)
+        .into_iter()
+        .map(PresetChange::Field)
+        .collect();
+    diff_set("tags", &before.tags, &after.tags, &mut changes);
+    diff_loras(&before.model.loras, &after.model.loras, &mut changes);
+    diff_set("prompt.styles", &before.prompt.styles, &after.prompt.styles, &mut changes);
+    diff_prompt(false, &before.prompt.positive, &after.prompt.positive, &mut changes);
+    diff_prompt(true, &before.prompt.negative, &after.prompt.negative, &mut changes);
+    Ok(changes)
+}
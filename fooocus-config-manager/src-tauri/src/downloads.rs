@@ -0,0 +1,572 @@
+use crate::database::{ModelInfo, ModelType, ResourceDownloads};
+use crate::model_files::sha256_file;
+use crate::resources::check_file_name;
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::fs::OpenOptions;
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::Duration;
+
+pub const DEFAULT_CONCURRENCY: usize = 2;
+
+pub const FOOOCUS_PATHS_SETTING: &str = "fooocus_paths";
+
+pub const PROGRESS_EVENT: &str = "download-progress";
+
+const PROGRESS_STEP: u64 = 1024 * 1024;
+
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Longest wait for the next bytes of a response; whole downloads may take hours.
+const READ_TIMEOUT: Duration = Duration::from_secs(60);
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FooocusPaths {
+    pub checkpoints_dir: String,
+    pub loras_dir: String,
+    pub embeddings_dir: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum DownloadKind {
+    Checkpoint,
+    Lora,
+    Embedding,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DownloadRequest {
+    pub kind: DownloadKind,
+    pub file_name: String,
+    pub url: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sha256: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "camelCase", rename_all_fields = "camelCase")]
+pub enum DownloadStatus {
+    Queued,
+    Downloading { downloaded: u64, total: Option<u64> },
+    Verifying,
+    Completed,
+    /// The file was already in place; nothing was downloaded.
+    Skipped,
+    Failed { message: String },
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DownloadProgress {
+    pub file_name: String,
+    pub kind: DownloadKind,
+    pub status: DownloadStatus,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DownloadResult {
+    pub request: DownloadRequest,
+    pub status: DownloadStatus,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub path: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DownloadReport {
+    pub results: Vec<DownloadResult>,
+    pub registered: Vec<ModelInfo>,
+}
+
+impl DownloadKind {
+    pub fn model_type(self) -> ModelType {
+        match self {
+            DownloadKind::Checkpoint => ModelType::Checkpoint,
+            DownloadKind::Lora => ModelType::LoRA,
+            DownloadKind::Embedding => ModelType::Embedding,
+        }
+    }
+}
+
+impl FooocusPaths {
+    pub fn dir_for(&self, kind: DownloadKind) -> Result<PathBuf, String> {
+        let dir = match kind {
+            DownloadKind::Checkpoint => &self.checkpoints_dir,
+            DownloadKind::Lora => &self.loras_dir,
+            DownloadKind::Embedding => &self.embeddings_dir,
+        };
+        if dir.trim().is_empty() {
+            return Err(format!("No Fooocus folder configured for {} files", kind.model_type()));
+        }
+        Ok(PathBuf::from(dir))
+    }
+}
+
+pub fn requests_from_resources(resources: &ResourceDownloads) -> Vec<DownloadRequest> {
+    resources.maps()
+        .into_iter()
+        .flat_map(|(kind, map)| map.into_iter().flatten().map(move |(file_name, entry)| DownloadRequest {
+            kind,
+            file_name: file_name.clone(),
+            url: entry.url.clone(),
+            sha256: entry.sha256.clone(),
+        }))
+        .collect()
+}
+
+fn verify(path: &Path, expected: &str) -> Result<(), String> {
+    let actual = sha256_file(path).map_err(|e| e.to_string())?;
+    if !actual.eq_ignore_ascii_case(expected.trim()) {
+        return Err(format!("SHA-256 mismatch: expected {}, got {}", expected.trim(), actual));
+    }
+    Ok(())
+}
+
+/// Downloads one file into `dir`, resuming from `<file>.part` when a previous
+/// attempt was interrupted. `on_progress` receives the byte counts as they grow.
+pub fn download_file(
+    request: &DownloadRequest,
+    dir: &Path,
+    on_progress: &dyn Fn(DownloadStatus),
+) -> Result<(PathBuf, DownloadStatus), String> {
+    check_file_name(&request.file_name)?;
+    std::fs::create_dir_all(dir).map_err(|e| e.to_string())?;
+
+    let target = dir.join(&request.file_name);
+    if target.is_file() {
+        if let Some(expected) = &request.sha256 {
+            on_progress(DownloadStatus::Verifying);
+            verify(&target, expected).map_err(|e| format!("Existing file {}: {}", target.display(), e))?;
+        }
+        return Ok((target, DownloadStatus::Skipped));
+    }
+
+    let part = dir.join(format!("{}.part", request.file_name));
+    let resume_from = std::fs::metadata(&part).map(|m| m.len()).unwrap_or(0);
+
+    let agent = ureq::AgentBuilder::new()
+        .timeout_connect(CONNECT_TIMEOUT)
+        .timeout_read(READ_TIMEOUT)
+        .build();
+    let mut call = agent.get(&request.url);
+    if resume_from > 0 {
+        call = call.set("Range", &format!("bytes={}-", resume_from));
+    }
+    let (response, append) = match call.call() {
+        Ok(response) if response.status() == 206 => (Some(response), true),
+        Ok(response) => (Some(response), false),
+        Err(ureq::Error::Status(416, response)) if resume_from > 0 => {
+            // The part file holds the whole body only if it is exactly as long.
+            let total = response.header("Content-Range").and_then(content_range_total);
+            if total != Some(resume_from) {
+                std::fs::remove_file(&part).map_err(|e| e.to_string())?;
+                return download_file(request, dir, on_progress);
+            }
+            (None, true)
+        }
+        Err(e) => return Err(format!("Failed to download {}: {}", request.url, e)),
+    };
+
+    if let Some(response) = response {
+        if append {
+            let start = response.header("Content-Range").and_then(content_range_start);
+            if start != Some(resume_from) {
+                std::fs::remove_file(&part).ok();
+                return Err(format!(
+                    "Server resumed {} at the wrong offset; the download will restart",
+                    request.url
+                ));
+            }
+        }
+        let remaining: Option<u64> = response.header("Content-Length").and_then(|l| l.parse().ok());
+        let mut downloaded = if append { resume_from } else { 0 };
+        let total = remaining.map(|r| r + downloaded);
+
+        let mut file = OpenOptions::new()
+            .create(true)
+            .write(true)
+            .append(append)
+            .truncate(!append)
+            .open(&part)
+            .map_err(|e| e.to_string())?;
+        let mut reader = response.into_reader();
+        let mut buffer = vec![0u8; 64 * 1024];
+        let mut reported = downloaded;
+        on_progress(DownloadStatus::Downloading { downloaded, total });
+
+        loop {
+            let read = reader.read(&mut buffer).map_err(|e| e.to_string())?;
+            if read == 0 {
+                break;
+            }
+            file.write_all(&buffer[..read]).map_err(|e| e.to_string())?;
+            downloaded += read as u64;
+            if downloaded - reported >= PROGRESS_STEP {
+                reported = downloaded;
+                on_progress(DownloadStatus::Downloading { downloaded, total });
+            }
+        }
+        file.flush().map_err(|e| e.to_string())?;
+        on_progress(DownloadStatus::Downloading { downloaded, total });
+
+        if total.is_some_and(|t| downloaded < t) {
+            return Err(format!("Connection closed after {} bytes; the download can be resumed", downloaded));
+        }
+    }
+
+    if let Some(expected) = &request.sha256 {
+        on_progress(DownloadStatus::Verifying);
+        if let Err(e) = verify(&part, expected) {
+            std::fs::remove_file(&part).ok();
+            return Err(e);
+        }
+    }
+
+    std::fs::rename(&part, &target).map_err(|e| e.to_string())?;
+    Ok((target, DownloadStatus::Completed))
+}
+
+/// The first byte position of a `Content-Range: bytes START-END/TOTAL` header.
+fn content_range_start(value: &str) -> Option<u64> {
+    value.trim()
+        .strip_prefix("bytes ")?
+        .split_once('-')?
+        .0
+        .trim()
+        .parse()
+        .ok()
+}
+
+/// The full length in an unsatisfied range's `Content-Range: bytes */TOTAL` header.
+fn content_range_total(value: &str) -> Option<u64> {
+    value.trim().strip_prefix("bytes */")?.trim().parse().ok()
+}
+
+/// Runs the requests on at most `concurrency` worker threads and returns the
+/// results in request order.
+pub fn run_downloads<F>(
+    requests: Vec<DownloadRequest>,
+    paths: &FooocusPaths,
+    concurrency: usize,
+    on_progress: F,
+) -> Vec<DownloadResult>
+where
+    F: Fn(DownloadProgress) + Sync,
+{
+    for request in &requests {
+        on_progress(DownloadProgress {
+            file_name: request.file_name.clone(),
+            kind: request.kind,
+            status: DownloadStatus::Queued,
+        });
+    }
+
+    let queue = Mutex::new(requests.iter().enumerate().collect::<VecDeque<_>>());
+    let results: Mutex<Vec<Option<DownloadResult>>> = Mutex::new(vec![None; requests.len()]);
+    let workers = concurrency.clamp(1, requests.len().max(1));
+
+    std::thread::scope(|scope| {
+        for _ in 0..workers {
+            scope.spawn(|| {
+                let next = || queue.lock().ok().and_then(|mut q| q.pop_front());
+                while let Some((index, request)) = next() {
+                    let report = |status: DownloadStatus| on_progress(DownloadProgress {
+                        file_name: request.file_name.clone(),
+                        kind: request.kind,
+                        status,
+                    });
+
+                    let outcome = paths.dir_for(request.kind)
+                        .and_then(|dir| download_file(request, &dir, &report));
+                    let (status, path) = match outcome {
+                        Ok((path, status)) => (status, Some(path.to_string_lossy().to_string())),
+                        Err(message) => (DownloadStatus::Failed { message }, None),
+                    };
+                    report(status.clone());
+
+                    if let Ok(mut results) = results.lock() {
+                        results[index] = Some(DownloadResult {
+                            request: request.clone(),
+                            status,
+                            path,
+                        });
+                    }
+                }
+            });
+        }
+    });
+
+    results.into_inner()
+        .unwrap_or_default()
+        .into_iter()
+        .flatten()
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sha2::{Digest, Sha256};
+    use std::io::{BufRead, BufReader};
+    use std::net::TcpListener;
+    use std::sync::Arc;
+
+    /// Minimal HTTP/1.1 server that serves `body` for any path and honours
+    /// `Range: bytes=N-`. Every Range header it sees is recorded.
+    struct TestServer {
+        url: String,
+        ranges: Arc<Mutex<Vec<Option<String>>>>,
+    }
+
+    fn serve(body: Vec<u8>) -> TestServer {
+        serve_from(body, 0)
+    }
+
+    /// Like `serve`, but answers ranges `skew` bytes before the requested start.
+    fn serve_from(body: Vec<u8>, skew: usize) -> TestServer {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        let ranges = Arc::new(Mutex::new(Vec::new()));
+        let seen = ranges.clone();
+
+        std::thread::spawn(move || {
+            for stream in listener.incoming() {
+                let Ok(mut stream) = stream else { continue };
+                let mut reader = BufReader::new(stream.try_clone().unwrap());
+                let mut range = None;
+                loop {
+                    let mut line = String::new();
+                    if reader.read_line(&mut line).unwrap_or(0) == 0 || line == "\r\n" {
+                        break;
+                    }
+                    if let Some((name, value)) = line.split_once(':') {
+                        if name.eq_ignore_ascii_case("range") {
+                            range = Some(value.trim().to_string());
+                        }
+                    }
+                }
+                seen.lock().unwrap().push(range.clone());
+
+                let start = range.as_deref()
+                    .and_then(|r| r.strip_prefix("bytes="))
+                    .and_then(|r| r.trim_end_matches('-').parse::<usize>().ok())
+                    .map(|start| start.saturating_sub(skew));
+                let head = match start {
+                    Some(start) if start >= body.len() => {
+                        format!(
+                            "HTTP/1.1 416 Range Not Satisfiable\r\nContent-Range: bytes */{}\r\nContent-Length: 0\r\nConnection: close\r\n\r\n",
+                            body.len()
+                        )
+                    }
+                    Some(start) => format!(
+                        "HTTP/1.1 206 Partial Content\r\nContent-Length: {}\r\nContent-Range: bytes {}-{}/{}\r\nConnection: close\r\n\r\n",
+                        body.len() - start, start, body.len() - 1, body.len()
+                    ),
+                    None => format!("HTTP/1.1 200 OK\r\nContent-Length: {}\r\nConnection: close\r\n\r\n", body.len()),
+                };
+                stream.write_all(head.as_bytes()).ok();
+                match start {
+                    Some(start) if start >= body.len() => {}
+                    Some(start) => { stream.write_all(&body[start..]).ok(); }
+                    None => { stream.write_all(&body).ok(); }
+                }
+            }
+        });
+
+        TestServer { url, ranges }
+    }
+
+    fn body() -> Vec<u8> {
+        (0..300_000u32).map(|i| (i % 251) as u8).collect()
+    }
+
+    fn sha256(bytes: &[u8]) -> String {
+        format!("{:x}", Sha256::digest(bytes))
+    }
+
+    fn temp_dir() -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("downloads-test-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn request(server: &TestServer, file_name: &str, sha256: Option<String>) -> DownloadRequest {
+        DownloadRequest {
+            kind: DownloadKind::Lora,
+            file_name: file_name.to_string(),
+            url: format!("{}/{}", server.url, file_name),
+            sha256,
+        }
+    }
+
+    #[test]
+    fn downloads_and_verifies_checksum() {
+        let body = body();
+        let server = serve(body.clone());
+        let dir = temp_dir();
+
+        let (path, status) = download_file(&request(&server, "a.safetensors", Some(sha256(&body))), &dir, &|_| {}).unwrap();
+
+        assert_eq!(status, DownloadStatus::Completed);
+        assert_eq!(std::fs::read(&path).unwrap(), body);
+        assert!(!dir.join("a.safetensors.part").exists());
+        std::fs::remove_dir_all(&dir).ok();
+    }
+
+    #[test]
+    fn resumes_from_partial_file() {
+        let body = body();
+        let server = serve(body.clone());
+        let dir = temp_dir();
+        std::fs::write(dir.join("b.safetensors.part"), &body[..100_000]).unwrap();
+
+        let (path, _) = download_file(&request(&server, "b.safetensors", Some(sha256(&body))), &dir, &|_| {}).unwrap();
+
+        assert_eq!(std::fs::read(&path).unwrap(), body);
+        assert_eq!(server.ranges.lock().unwrap().as_slice(), &[Some("bytes=100000-".to_string())]);
+        std::fs::remove_dir_all(&dir).ok();
+    }
+
+    #[test]
+    fn restarts_when_server_resumes_at_wrong_offset() {
+        let body = body();
+        let server = serve_from(body.clone(), 1000);
+        let dir = temp_dir();
+        std::fs::write(dir.join("f.safetensors.part"), &body[..100_000]).unwrap();
+
+        let result = download_file(&request(&server, "f.safetensors", None), &dir, &|_| {});
+
+        assert!(result.unwrap_err().contains("wrong offset"));
+        assert!(!dir.join("f.safetensors.part").exists());
+        assert!(!dir.join("f.safetensors").exists());
+        std::fs::remove_dir_all(&dir).ok();
+    }
+
+    #[test]
+    fn completes_when_partial_file_is_already_full() {
+        let body = body();
+        let server = serve(body.clone());
+        let dir = temp_dir();
+        std::fs::write(dir.join("c.safetensors.part"), &body).unwrap();
+
+        let (path, status) = download_file(&request(&server, "c.safetensors", None), &dir, &|_| {}).unwrap();
+
+        assert_eq!(status, DownloadStatus::Completed);
+        assert_eq!(std::fs::read(&path).unwrap(), body);
+        std::fs::remove_dir_all(&dir).ok();
+    }
+
+    #[test]
+    fn restarts_when_partial_file_is_longer_than_body() {
+        let body = body();
+        let server = serve(body.clone());
+        let dir = temp_dir();
+        std::fs::write(dir.join("h.safetensors.part"), vec![7u8; body.len() + 10]).unwrap();
+
+        let (path, status) = download_file(&request(&server, "h.safetensors", None), &dir, &|_| {}).unwrap();
+
+        assert_eq!(status, DownloadStatus::Completed);
+        assert_eq!(std::fs::read(&path).unwrap(), body);
+        assert_eq!(
+            server.ranges.lock().unwrap().as_slice(),
+            &[Some(format!("bytes={}-", body.len() + 10)), None]
+        );
+        std::fs::remove_dir_all(&dir).ok();
+    }
+
+    #[test]
+    fn rejects_checksum_mismatch() {
+        let server = serve(body());
+        let dir = temp_dir();
+
+        let result = download_file(&request(&server, "d.safetensors", Some("00".repeat(32))), &dir, &|_| {});
+
+        assert!(result.unwrap_err().contains("SHA-256 mismatch"));
+        assert!(!dir.join("d.safetensors").exists());
+        assert!(!dir.join("d.safetensors.part").exists());
+        std::fs::remove_dir_all(&dir).ok();
+    }
+
+    #[test]
+    fn skips_existing_files_without_requesting_them() {
+        let server = serve(body());
+        let dir = temp_dir();
+        std::fs::write(dir.join("e.safetensors"), b"already here").unwrap();
+
+        let (_, status) = download_file(&request(&server, "e.safetensors", None), &dir, &|_| {}).unwrap();
+
+        assert_eq!(status, DownloadStatus::Skipped);
+        assert!(server.ranges.lock().unwrap().is_empty());
+        std::fs::remove_dir_all(&dir).ok();
+    }
+
+    #[test]
+    fn rejects_file_names_with_paths() {
+        let server = serve(body());
+        let dir = temp_dir();
+
+        assert!(download_file(&request(&server, "../escape.safetensors", None), &dir, &|_| {}).is_err());
+        assert!(download_file(&request(&server, "sub/dir.safetensors", None), &dir, &|_| {}).is_err());
+        std::fs::remove_dir_all(&dir).ok();
+    }
+
+    #[test]
+    fn runs_queue_with_limited_workers_and_reports_progress() {
+        let body = body();
+        let server = serve(body.clone());
+        let dir = temp_dir();
+        let paths = FooocusPaths {
+            loras_dir: dir.to_string_lossy().to_string(),
+            ..FooocusPaths::default()
+        };
+        let requests: Vec<DownloadRequest> = (0..5)
+            .map(|i| request(&server, &format!("f{}.safetensors", i), None))
+            .collect();
+        let mut missing_folder = request(&server, "g.safetensors", None);
+        missing_folder.kind = DownloadKind::Checkpoint;
+
+        let events = Mutex::new(Vec::new());
+        let results = run_downloads(
+            requests.into_iter().chain([missing_folder]).collect(),
+            &paths,
+            2,
+            |progress| events.lock().unwrap().push(progress),
+        );
+
+        assert_eq!(results.len(), 6);
+        for (i, result) in results.iter().take(5).enumerate() {
+            assert_eq!(result.request.file_name, format!("f{}.safetensors", i));
+            assert_eq!(result.status, DownloadStatus::Completed);
+            assert_eq!(std::fs::read(result.path.as_ref().unwrap()).unwrap(), body);
+        }
+        assert!(matches!(results[5].status, DownloadStatus::Failed { .. }));
+
+        let events = events.into_inner().unwrap();
+        assert_eq!(events.iter().filter(|e| e.status == DownloadStatus::Queued).count(), 6);
+        assert_eq!(events.iter().filter(|e| e.status == DownloadStatus::Completed).count(), 5);
+        std::fs::remove_dir_all(&dir).ok();
+    }
+
+    #[test]
+    fn reads_plain_and_hashed_resource_entries() {
+        let resources: ResourceDownloads = serde_json::from_value(serde_json::json!({
+            "checkpointDownloads": { "base.safetensors": "https://example.com/base" },
+            "loraDownloads": {
+                "detail.safetensors": { "url": "https://example.com/detail", "sha256": "abc" }
+            }
+        })).unwrap();
+
+        let requests = requests_from_resources(&resources);
+
+        assert_eq!(requests.len(), 2);
+        assert_eq!(requests[0].kind, DownloadKind::Checkpoint);
+        assert_eq!(requests[0].sha256, None);
+        assert_eq!(requests[1].sha256.as_deref(), Some("abc"));
+    }
+}
@@ -0,0 +1,140 @@
+use crate::database::{ModelInfo, ModelType, PresetConfig, PromptConfig};
+use crate::linking::{strip_model_extension, ReferenceChange};
+use serde::{Deserialize, Serialize};
+
+const EMBEDDING_PREFIX: &str = "embedding:";
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EmbeddingReference {
+    pub name: String,
+    pub negative: bool,
+}
+
+fn is_name_char(c: char) -> bool {
+    c.is_alphanumeric() || matches!(c, '_' | '-' | '.')
+}
+
+/// The `embedding:name` tokens in `text`, as Fooocus reads them, including
+/// weighted forms such as `(embedding:name:1.2)`. Each is returned as the
+/// byte offset of its name and the name.
+fn embedding_tokens(text: &str) -> Vec<(usize, &str)> {
+    let mut tokens = Vec::new();
+
+    for (start, _) in text.match_indices(EMBEDDING_PREFIX) {
+        let starts_token = text[..start].chars().next_back()
+            .map(|c| !is_name_char(c))
+            .unwrap_or(true);
+        if !starts_token {
+            continue;
+        }
+
+        let name_start = start + EMBEDDING_PREFIX.len();
+        let rest = &text[name_start..];
+        let end = rest.find(|c: char| !is_name_char(c)).unwrap_or(rest.len());
+        let name = rest[..end].trim_end_matches('.');
+        if !name.is_empty() {
+            tokens.push((name_start, name));
+        }
+    }
+
+    tokens
+}
+
+/// Extracts the distinct names of the `embedding:name` tokens in `text`.
+pub fn parse_embeddings(text: &str) -> Vec<String> {
+    let mut names: Vec<String> = Vec::new();
+    for (_, name) in embedding_tokens(text) {
+        if !names.iter().any(|n| n == name) {
+            names.push(name.to_string());
+        }
+    }
+    names
+}
+
+/// Rewrites the names of the tokens for which `rename` returns a new one.
+fn rename_tokens(text: &str, mut rename: impl FnMut(&str) -> Option<String>) -> String {
+    let mut renamed = String::with_capacity(text.len());
+    let mut copied = 0;
+    for (start, name) in embedding_tokens(text) {
+        if let Some(new_name) = rename(name) {
+            renamed.push_str(&text[copied..start]);
+            renamed.push_str(&new_name);
+            copied = start + name.len();
+        }
+    }
+    renamed.push_str(&text[copied..]);
+    renamed
+}
+
+pub fn prompt_embeddings(prompt: &PromptConfig) -> Vec<EmbeddingReference> {
+    let positive = parse_embeddings(&prompt.positive).into_iter()
+        .map(|name| EmbeddingReference { name, negative: false });
+    let negative = parse_embeddings(&prompt.negative).into_iter()
+        .map(|name| EmbeddingReference { name, negative: true });
+    positive.chain(negative).collect()
+}
+
+/// Finds the `Embedding` model a token refers to: an exact file stem match wins,
+/// otherwise a unique case-insensitive one.
+pub fn resolve_embedding<'a>(name: &str, models: &'a [ModelInfo]) -> Option<&'a ModelInfo> {
+    let name = strip_model_extension(name);
+    let embeddings: Vec<&ModelInfo> = models.iter()
+        .filter(|m| m.model_type == ModelType::Embedding)
+        .collect();
+
+    if let Some(model) = embeddings.iter().find(|m| strip_model_extension(&m.file_name) == name) {
+        return Some(model);
+    }
+    let mut found = embeddings.into_iter()
+        .filter(|m| strip_model_extension(&m.file_name).eq_ignore_ascii_case(name));
+    match (found.next(), found.next()) {
+        (Some(model), None) => Some(model),
+        _ => None,
+    }
+}
+
+/// Whether an embedding token in the prompts refers to the model.
+pub fn prompt_references_model(prompt: &PromptConfig, model_id: &str, models: &[ModelInfo]) -> bool {
+    prompt_embeddings(prompt).iter()
+        .any(|e| resolve_embedding(&e.name, models).is_some_and(|m| m.id == model_id))
+}
+
+/// Whether a preset uses a model, either through its model config or through
+/// an embedding token in its prompts.
+pub fn preset_references_model(preset: &PresetConfig, model_id: &str, models: &[ModelInfo]) -> bool {
+    preset.model.references_model(model_id) || prompt_references_model(&preset.prompt, model_id, models)
+}
+
+/// Points the prompt tokens that refer to `model_id` at `replacement` instead
+/// and returns the renames.
+pub fn replace_embedding(prompt: &mut PromptConfig, model_id: &str, replacement: &ModelInfo, models: &[ModelInfo]) -> Vec<ReferenceChange> {
+    let new_name = strip_model_extension(&replacement.file_name).to_string();
+    let mut changes = Vec::new();
+    for (negative, text) in [(false, &mut prompt.positive), (true, &mut prompt.negative)] {
+        *text = rename_tokens(text, |name| {
+            let refers = resolve_embedding(name, models).is_some_and(|m| m.id == model_id);
+            if !refers || name == new_name {
+                return None;
+            }
+            let change = ReferenceChange::Embedding { negative, before: name.to_string(), after: new_name.clone() };
+            if !changes.contains(&change) {
+                changes.push(change);
+            }
+            Some(new_name.clone())
+        });
+    }
+    changes
+}
+
+/// Renames the `embedding:from` tokens of the positive or negative prompt to
+/// `to`. Returns whether there were any.
+pub fn rename_embedding(prompt: &mut PromptConfig, negative: bool, from: &str, to: &str) -> bool {
+    let text = if negative { &mut prompt.negative } else { &mut prompt.positive };
+    let mut renamed = false;
+    *text = rename_tokens(text, |name| (name == from).then(|| {
+        renamed = true;
+        to.to_string()
+    }));
+    renamed
+}
@@ -0,0 +1,256 @@
+use crate::database::{ModelConfig, ModelInfo, PresetConfig};
+use crate::linking::ModelSlot;
+use rusqlite::{params, Connection, OptionalExtension, Result as SqliteResult};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+
+/// One version of a family; its position in `ModelFamily::versions` is its order,
+/// oldest first.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FamilyVersion {
+    pub model_id: String,
+    pub label: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ModelFamily {
+    pub id: String,
+    pub name: String,
+    pub description: String,
+    pub versions: Vec<FamilyVersion>,
+    pub created_at: String,
+    pub updated_at: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ReferenceUpgrade {
+    pub slot: ModelSlot,
+    pub from_model_id: String,
+    pub from_file_name: String,
+    pub from_version: String,
+    pub to_model_id: String,
+    pub to_file_name: String,
+    pub to_version: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PresetUpgrade {
+    pub preset_id: String,
+    pub preset_name: String,
+    pub changes: Vec<ReferenceUpgrade>,
+}
+
+impl ModelFamily {
+    fn position(&self, model_id: &str) -> Option<usize> {
+        self.versions.iter().position(|v| v.model_id == model_id)
+    }
+
+    /// The version presets are moved to: the given model, or the newest one.
+    pub fn target(&self, model_id: Option<&str>) -> Result<usize, String> {
+        match model_id {
+            Some(id) => self.position(id)
+                .ok_or_else(|| format!("Model {} is not a version of {}", id, self.name)),
+            None if self.versions.is_empty() => Err(format!("Family {} has no versions", self.name)),
+            None => Ok(self.versions.len() - 1),
+        }
+    }
+}
+
+fn load_versions(conn: &Connection, family_id: &str) -> SqliteResult<Vec<FamilyVersion>> {
+    let mut stmt = conn.prepare(
+        "SELECT v.model_id, v.label FROM model_family_versions v JOIN models m ON m.id = v.model_id
+         WHERE v.family_id = ?1 AND m.deleted_at IS NULL ORDER BY v.position"
+    )?;
+    let versions = stmt.query_map(params![family_id], |row| {
+        Ok(FamilyVersion {
+            model_id: row.get(0)?,
+            label: row.get(1)?,
+        })
+    })?;
+    versions.collect()
+}
+
+fn family_from_row(conn: &Connection, row: (String, String, String, String, String)) -> SqliteResult<ModelFamily> {
+    let (id, name, description, created_at, updated_at) = row;
+    Ok(ModelFamily {
+        versions: load_versions(conn, &id)?,
+        id,
+        name,
+        description,
+        created_at,
+        updated_at,
+    })
+}
+
+pub fn load_families(conn: &Connection) -> SqliteResult<Vec<ModelFamily>> {
+    let rows: Vec<(String, String, String, String, String)> = {
+        let mut stmt = conn.prepare(
+            "SELECT id, name, description, created_at, updated_at FROM model_families ORDER BY name"
+        )?;
+        let rows = stmt.query_map([], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?, row.get(4)?)))?;
+        rows.collect::<SqliteResult<_>>()?
+    };
+    rows.into_iter().map(|row| family_from_row(conn, row)).collect()
+}
+
+pub fn load_family(conn: &Connection, id: &str) -> SqliteResult<Option<ModelFamily>> {
+    let row = conn.query_row(
+        "SELECT id, name, description, created_at, updated_at FROM model_families WHERE id = ?1",
+        params![id],
+        |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?, row.get(4)?)),
+    ).optional()?;
+    row.map(|row| family_from_row(conn, row)).transpose()
+}
+
+/// Checks that every version is a known model, listed once, not claimed by
+/// another family, and of the same type as the others.
+pub fn validate_family(conn: &Connection, family: &ModelFamily, models: &[ModelInfo]) -> Result<(), String> {
+    if family.name.trim().is_empty() {
+        return Err("Family name cannot be empty".to_string());
+    }
+
+    let mut seen = HashSet::new();
+    let mut family_type = None;
+    for version in &family.versions {
+        if !seen.insert(version.model_id.as_str()) {
+            return Err(format!("Model {} is listed twice", version.model_id));
+        }
+        let model = models.iter()
+            .find(|m| m.id == version.model_id)
+            .ok_or_else(|| format!("Model not found: {}", version.model_id))?;
+        match family_type {
+            None => family_type = Some(model.model_type),
+            Some(t) if t != model.model_type => {
+                return Err(format!("{} is a {}, but this family holds {} models", model.name, model.model_type, t));
+            }
+            Some(_) => {}
+        }
+
+        let owner: Option<String> = conn.query_row(
+            "SELECT f.name FROM model_family_versions v JOIN model_families f ON f.id = v.family_id
+             WHERE v.model_id = ?1 AND v.family_id != ?2",
+            params![version.model_id, family.id],
+            |row| row.get(0),
+        ).optional().map_err(|e| e.to_string())?;
+        if let Some(owner) = owner {
+            return Err(format!("{} already belongs to family {}", model.name, owner));
+        }
+    }
+    Ok(())
+}
+
+/// Writes the family row and replaces its list of live versions. Runs inside
+/// the caller's transaction.
+pub fn save_family(conn: &Connection, family: &ModelFamily) -> SqliteResult<()> {
+    conn.execute(
+        "INSERT INTO model_families (id, name, description, created_at, updated_at)
+         VALUES (?1, ?2, ?3, ?4, ?5)
+         ON CONFLICT(id) DO UPDATE SET name = ?2, description = ?3, updated_at = ?5",
+        params![family.id, family.name, family.description, family.created_at, family.updated_at],
+    )?;
+    // Trashed models are not listed, so their rows are kept for a restore and
+    // moved after the live versions.
+    let trashed: Vec<String> = {
+        let mut stmt = conn.prepare(
+            "SELECT v.model_id FROM model_family_versions v JOIN models m ON m.id = v.model_id
+             WHERE v.family_id = ?1 AND m.deleted_at IS NOT NULL ORDER BY v.position"
+        )?;
+        let rows = stmt.query_map(params![family.id], |row| row.get(0))?;
+        rows.collect::<SqliteResult<_>>()?
+    };
+    conn.execute(
+        "DELETE FROM model_family_versions WHERE family_id = ?1
+         AND model_id NOT IN (SELECT id FROM models WHERE deleted_at IS NOT NULL)",
+        params![family.id],
+    )?;
+    for (position, version) in family.versions.iter().enumerate() {
+        conn.execute(
+            "INSERT INTO model_family_versions (family_id, model_id, label, position) VALUES (?1, ?2, ?3, ?4)",
+            params![family.id, version.model_id, version.label, position as i64],
+        )?;
+    }
+    for (offset, model_id) in trashed.iter().enumerate() {
+        conn.execute(
+            "UPDATE model_family_versions SET position = ?1 WHERE family_id = ?2 AND model_id = ?3",
+            params![(family.versions.len() + offset) as i64, family.id, model_id],
+        )?;
+    }
+    Ok(())
+}
+
+/// Lists the references in `model` that point at a version older than `target`.
+fn upgrades_for(model: &ModelConfig, family: &ModelFamily, target: usize, models: &[ModelInfo]) -> Vec<ReferenceUpgrade> {
+    let to = &family.versions[target];
+    let file_name = |id: &str| models.iter()
+        .find(|m| m.id == id)
+        .map(|m| m.file_name.clone())
+        .unwrap_or_default();
+
+    let mut references: Vec<(ModelSlot, Option<&String>)> = vec![
+        (ModelSlot::Base, model.base_model_id.as_ref()),
+        (ModelSlot::Refiner, model.refiner_model_id.as_ref()),
+        (ModelSlot::Vae, model.vae_model_id.as_ref()),
+    ];
+    references.extend(model.loras.iter().enumerate().map(|(index, l)| (ModelSlot::Lora { index }, l.model_id.as_ref())));
+
+    references.into_iter()
+        .filter_map(|(slot, id)| {
+            let position = family.position(id?)?;
+            (position < target).then(|| {
+                let from = &family.versions[position];
+                ReferenceUpgrade {
+                    slot,
+                    from_model_id: from.model_id.clone(),
+                    from_file_name: file_name(&from.model_id),
+                    from_version: from.label.clone(),
+                    to_model_id: to.model_id.clone(),
+                    to_file_name: file_name(&to.model_id),
+                    to_version: to.label.clone(),
+                }
+            })
+        })
+        .collect()
+}
+
+/// Every preset that uses an older version of the family than `target`.
+pub fn plan_upgrades(family: &ModelFamily, target: usize, presets: &[PresetConfig], models: &[ModelInfo]) -> Vec<PresetUpgrade> {
+    presets.iter()
+        .filter_map(|preset| {
+            let changes = upgrades_for(&preset.model, family, target, models);
+            (!changes.is_empty()).then(|| PresetUpgrade {
+                preset_id: preset.id.clone(),
+                preset_name: preset.name.clone(),
+                changes,
+            })
+        })
+        .collect()
+}
+
+pub fn apply_upgrade(model: &mut ModelConfig, changes: &[ReferenceUpgrade]) {
+    for change in changes {
+        match change.slot {
+            ModelSlot::Base => {
+                model.base_model = change.to_file_name.clone();
+                model.base_model_id = Some(change.to_model_id.clone());
+            }
+            ModelSlot::Refiner => {
+                model.refiner_model = change.to_file_name.clone();
+                model.refiner_model_id = Some(change.to_model_id.clone());
+            }
+            ModelSlot::Vae => {
+                model.vae = change.to_file_name.clone();
+                model.vae_model_id = Some(change.to_model_id.clone());
+            }
+            ModelSlot::Lora { index } => {
+                if let Some(lora) = model.loras.get_mut(index) {
+                    lora.model_name = change.to_file_name.clone();
+                    lora.model_id = Some(change.to_model_id.clone());
+                }
+            }
+        }
+    }
+}
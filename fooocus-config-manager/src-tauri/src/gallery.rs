@@ -0,0 +1,89 @@
+use rusqlite::{params, Connection, Result as SqliteResult, Row};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PresetImage {
+    pub id: String,
+    pub preset_id: String,
+    pub path: String,
+    /// Whether `path` is a copy owned by the app rather than a reference to the user's file.
+    pub is_copy: bool,
+    pub is_cover: bool,
+    pub notes: String,
+    pub rating: Option<u8>,
+    pub sort_order: i32,
+    pub created_at: String,
+}
+
+pub const PRESET_IMAGE_COLUMNS: &str = "id, preset_id, path, is_copy, is_cover, notes, rating, sort_order, created_at";
+
+impl PresetImage {
+    pub fn from_row(row: &Row) -> SqliteResult<Self> {
+        Ok(PresetImage {
+            id: row.get(0)?,
+            preset_id: row.get(1)?,
+            path: row.get(2)?,
+            is_copy: row.get::<_, i32>(3)? != 0,
+            is_cover: row.get::<_, i32>(4)? != 0,
+            notes: row.get::<_, Option<String>>(5)?.unwrap_or_default(),
+            rating: row.get(6)?,
+            sort_order: row.get(7)?,
+            created_at: row.get(8)?,
+        })
+    }
+}
+
+pub fn load_preset_images(conn: &Connection, preset_id: &str) -> SqliteResult<Vec<PresetImage>> {
+    let mut stmt = conn.prepare(&format!(
+        "SELECT {} FROM preset_images WHERE preset_id = ?1 ORDER BY sort_order, created_at",
+        PRESET_IMAGE_COLUMNS
+    ))?;
+    let images = stmt.query_map(params![preset_id], PresetImage::from_row)?;
+    images.collect()
+}
+
+pub fn load_preset_image(conn: &Connection, image_id: &str) -> SqliteResult<PresetImage> {
+    conn.query_row(
+        &format!("SELECT {} FROM preset_images WHERE id = ?1", PRESET_IMAGE_COLUMNS),
+        params![image_id],
+        PresetImage::from_row,
+    )
+}
+
+pub fn insert_preset_image(conn: &Connection, image: &PresetImage) -> SqliteResult<()> {
+    conn.execute(
+        "INSERT INTO preset_images (id, preset_id, path, is_copy, is_cover, notes, rating, sort_order, created_at)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
+        params![
+            image.id,
+            image.preset_id,
+            image.path,
+            image.is_copy as i32,
+            image.is_cover as i32,
+            image.notes,
+            image.rating,
+            image.sort_order,
+            image.created_at,
+        ],
+    )?;
+    Ok(())
+}
+
+/// Makes `image_id` the only cover image of its preset.
+pub fn set_cover(conn: &Connection, preset_id: &str, image_id: &str) -> SqliteResult<()> {
+    conn.execute(
+        "UPDATE preset_images SET is_cover = (id = ?1) WHERE preset_id = ?2",
+        params![image_id, preset_id],
+    )?;
+    Ok(())
+}
+
+/// Removes the files the app owns for an image: its copy (if any) and its thumbnails.
+pub fn remove_image_files(image: &PresetImage, thumbnail_dir: &Path) {
+    if image.is_copy {
+        std::fs::remove_file(&image.path).ok();
+    }
+    crate::thumbnails::remove_thumbnails(thumbnail_dir, &image.id);
+}
@@ -0,0 +1,328 @@
+use crate::database::{PresetConfig, PRESET_COLUMNS};
+use crate::diff;
+use crate::revisions;
+use rusqlite::{params, Connection, OptionalExtension};
+use serde_json::{Map, Value};
+
+/// Fields a child never takes from its parent.
+const OWN_FIELDS: &[&str] = &["name", "parentId", "inheritedFields"];
+/// Sections inherited field by field; other top-level fields are inherited whole.
+const SECTIONS: &[&str] = &["model", "sampling", "prompt", "image", "resources"];
+
+/// The inheritable fields present in a preset, as `description` or
+/// `sampling.steps` style paths.
+fn field_paths(value: &Value) -> Vec<String> {
+    let mut paths = Vec::new();
+    for (key, field) in value.as_object().into_iter().flatten() {
+        if OWN_FIELDS.contains(&key.as_str()) {
+            continue;
+        }
+        match field.as_object() {
+            Some(section) if SECTIONS.contains(&key.as_str()) => {
+                paths.extend(section.keys().map(|child| format!("{}.{}", key, child)));
+            }
+            _ => paths.push(key.clone()),
+        }
+    }
+    paths
+}
+
+fn get_field<'a>(value: &'a Value, path: &str) -> Option<&'a Value> {
+    value.pointer(&format!("/{}", path.replace('.', "/")))
+}
+
+fn set_field(value: &mut Value, path: &str, field: Option<Value>) {
+    let Some(object) = value.as_object_mut() else { return };
+    let (object, key) = match path.split_once('.') {
+        Some((section, key)) => {
+            let section = object.entry(section).or_insert_with(|| Value::Object(Map::new()));
+            match section.as_object_mut() {
+                Some(section) => (section, key),
+                None => return,
+            }
+        }
+        None => (object, path),
+    };
+    match field {
+        Some(field) => object.insert(key.to_string(), field),
+        None => object.remove(key),
+    };
+}
+
+fn union_paths(a: &Value, b: &Value) -> Vec<String> {
+    let mut paths = field_paths(a);
+    for path in field_paths(b) {
+        if !paths.contains(&path) {
+            paths.push(path);
+        }
+    }
+    paths
+}
+
+/// Fields a child sets itself: everything it has that is not inherited.
+pub fn overrides(preset: &PresetConfig) -> Vec<String> {
+    if preset.parent_id.is_none() {
+        return Vec::new();
+    }
+    let value = diff::settings_value(preset).unwrap_or_default();
+    field_paths(&value)
+        .into_iter()
+        .filter(|path| !preset.inherited_fields.contains(path))
+        .collect()
+}
+
+/// Inverse of `overrides`, for presets read back from their stored overrides.
+pub fn inherited_fields(preset: &PresetConfig, overrides: &[String]) -> Vec<String> {
+    if preset.parent_id.is_none() {
+        return Vec::new();
+    }
+    let value = diff::settings_value(preset).unwrap_or_default();
+    field_paths(&value)
+        .into_iter()
+        .filter(|path| !overrides.contains(path))
+        .collect()
+}
+
+/// Marks every field the child shares with its parent as inherited, so it
+/// follows later changes to the parent. Used when a child gets its parent.
+pub fn detect_inherited(parent: &PresetConfig, child: &mut PresetConfig) -> Result<(), String> {
+    let (parent, own) = (diff::settings_value(parent)?, diff::settings_value(child)?);
+    child.inherited_fields = union_paths(&parent, &own)
+        .into_iter()
+        .filter(|path| get_field(&parent, path) == get_field(&own, path))
+        .collect();
+    Ok(())
+}
+
+/// Works out the inherited fields of an edited child from the ones it had.
+/// Overrides stay overrides even where they equal the parent, inherited fields
+/// whose value was changed become overrides, and an override `edited` lists as
+/// inherited again is cleared and takes the parent's value.
+fn keep_overrides(parent: &PresetConfig, stored: &PresetConfig, edited: &PresetConfig) -> Result<PresetConfig, String> {
+    let stored_overrides = overrides(stored);
+    let (parent_value, own) = (diff::settings_value(parent)?, diff::settings_value(edited)?);
+    let inherited_fields = union_paths(&parent_value, &own)
+        .into_iter()
+        .filter(|path| {
+            edited.inherited_fields.contains(path)
+                && (stored_overrides.contains(path) || get_field(&parent_value, path) == get_field(&own, path))
+        })
+        .collect();
+    resolve(parent, &PresetConfig {
+        inherited_fields,
+        ..edited.clone()
+    })
+}
+
+/// Replaces the child's inherited fields with the parent's current values.
+/// Fields the parent has and the child does not override are inherited too.
+pub fn resolve(parent: &PresetConfig, child: &PresetConfig) -> Result<PresetConfig, String> {
+    let overrides = overrides(child);
+    let parent_value = diff::settings_value(parent)?;
+    let paths = union_paths(&parent_value, &diff::settings_value(child)?);
+    let mut value = serde_json::to_value(child).map_err(|e| e.to_string())?;
+    for path in &paths {
+        if !overrides.contains(path) {
+            set_field(&mut value, path, get_field(&parent_value, path).cloned());
+        }
+    }
+
+    let mut resolved: PresetConfig = serde_json::from_value(value).map_err(|e| e.to_string())?;
+    resolved.inherited_fields = paths.into_iter().filter(|path| !overrides.contains(path)).collect();
+    Ok(resolved)
+}
+
+fn load_preset(conn: &Connection, id: &str) -> Result<Option<PresetConfig>, String> {
+    conn.query_row(
+        &format!("SELECT {} FROM presets WHERE id = ?1 AND deleted_at IS NULL", PRESET_COLUMNS),
+        params![id],
+        PresetConfig::from_row,
+    ).optional().map_err(|e| e.to_string())
+}
+
+/// Checks that `parent_id` can be the parent of `id`: it exists and does not
+/// already inherit from `id`.
+pub fn check_parent(conn: &Connection, id: &str, parent_id: &str) -> Result<PresetConfig, String> {
+    let parent = load_preset(conn, parent_id)?
+        .ok_or_else(|| format!("Parent preset not found: {}", parent_id))?;
+
+    let mut ancestor = Some(parent_id.to_string());
+    while let Some(current) = ancestor {
+        if current == id {
+            return Err("A preset cannot inherit from itself or one of its descendants".to_string());
+        }
+        ancestor = conn.query_row(
+            "SELECT parent_id FROM presets WHERE id = ?1",
+            params![current],
+            |row| row.get(0),
+        ).optional().map_err(|e| e.to_string())?.flatten();
+    }
+
+    Ok(parent)
+}
+
+/// Sets up a preset that is about to be saved with a parent: checks the
+/// parent and works out its inherited fields. A child that keeps its parent
+/// keeps its overrides; a new child inherits what it shares with the parent.
+pub fn prepare_child(conn: &Connection, preset: &mut PresetConfig) -> Result<(), String> {
+    let Some(parent_id) = preset.parent_id.clone() else {
+        preset.inherited_fields.clear();
+        return Ok(());
+    };
+    let parent = check_parent(conn, &preset.id, &parent_id)?;
+    match load_preset(conn, &preset.id)?.filter(|stored| stored.parent_id == preset.parent_id) {
+        Some(stored) => {
+            *preset = keep_overrides(&parent, &stored, preset)?;
+            Ok(())
+        }
+        None => detect_inherited(&parent, preset),
+    }
+}
+
+/// Brings a preset's inherited fields up to date with its parent, e.g. when an
+/// older revision is restored.
+pub fn resolve_from_parent(conn: &Connection, preset: PresetConfig) -> Result<PresetConfig, String> {
+    match preset.parent_id.as_deref() {
+        Some(parent_id) => resolve(&check_parent(conn, &preset.id, parent_id)?, &preset),
+        None => Ok(preset),
+    }
+}
+
+/// Makes the children of a preset that is moved to the trash stand on their
+/// own, keeping their current settings. Trashed children are detached too, so
+/// restoring them later does not point them at a deleted parent.
+pub fn detach_children(conn: &Connection, parent_id: &str, now: &str) -> Result<Vec<PresetConfig>, String> {
+    let children: Vec<(PresetConfig, bool)> = {
+        let mut stmt = conn.prepare(&format!(
+            "SELECT {}, deleted_at IS NULL FROM presets WHERE parent_id = ?1",
+            PRESET_COLUMNS
+        )).map_err(|e| e.to_string())?;
+        let rows = stmt.query_map(params![parent_id], |row| Ok((PresetConfig::from_row(row)?, row.get(18)?)))
+            .map_err(|e| e.to_string())?;
+        rows.collect::<Result<_, _>>().map_err(|e| e.to_string())?
+    };
+
+    let mut detached = Vec::new();
+    for (child, live) in children {
+        let child = PresetConfig {
+            parent_id: None,
+            inherited_fields: vec![],
+            updated_at: now.to_string(),
+            ..child
+        };
+        revisions::save_preset(conn, &child, now).map_err(|e| e.to_string())?;
+        if live {
+            detached.push(child);
+        }
+    }
+    Ok(detached)
+}
+
+/// Re-resolves every descendant of `parent` after it changed, recording a
+/// revision for each child that changed with it.
+pub fn refresh_children(conn: &Connection, parent: &PresetConfig, now: &str) -> Result<Vec<PresetConfig>, String> {
+    let children: Vec<PresetConfig> = {
+        let mut stmt = conn.prepare(&format!(
+            "SELECT {} FROM presets WHERE parent_id = ?1 AND deleted_at IS NULL",
+            PRESET_COLUMNS
+        )).map_err(|e| e.to_string())?;
+        let rows = stmt.query_map(params![parent.id], PresetConfig::from_row).map_err(|e| e.to_string())?;
+        rows.collect::<Result<_, _>>().map_err(|e| e.to_string())?
+    };
+
+    let mut refreshed = Vec::new();
+    for child in children {
+        let mut resolved = resolve(parent, &child)?;
+        if diff::settings_value(&resolved)? == diff::settings_value(&child)? {
+            continue;
+        }
+        resolved.updated_at = now.to_string();
+        revisions::save_preset(conn, &resolved, now).map_err(|e| e.to_string())?;
+        refreshed.extend(refresh_children(conn, &resolved, now)?);
+        refreshed.push(resolved);
+    }
+    Ok(refreshed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::{insert_preset_row, open_in_memory, test_preset, update_preset_row};
+    use crate::trash::{self, TrashKind};
+
+    const NOW: &str = "2024-02-01T00:00:00+00:00";
+
+    #[test]
+    fn children_stay_editable_after_parent_is_trashed() {
+        let conn = open_in_memory().unwrap();
+        let mut parent = test_preset("parent");
+        parent.sampling.steps = 50;
+        insert_preset_row(&conn, &parent).unwrap();
+
+        let mut child = PresetConfig {
+            parent_id: Some(parent.id.clone()),
+            ..test_preset("child")
+        };
+        child.sampling.steps = 50;
+        prepare_child(&conn, &mut child).unwrap();
+        insert_preset_row(&conn, &child).unwrap();
+
+        // As `delete_preset` does.
+        assert!(trash::move_to_trash(&conn, TrashKind::Preset, &parent.id, NOW).unwrap());
+        let detached = detach_children(&conn, &parent.id, NOW).unwrap();
+        assert_eq!(detached.len(), 1);
+
+        // As `update_preset` does, with the child as the editor last loaded it.
+        let mut edited = load_preset(&conn, &child.id).unwrap().unwrap();
+        assert_eq!(edited.parent_id, None);
+        assert_eq!(edited.sampling.steps, 50);
+        edited.sampling.cfg_scale = 4.0;
+        prepare_child(&conn, &mut edited).unwrap();
+        update_preset_row(&conn, &edited).unwrap();
+
+        let saved = load_preset(&conn, &child.id).unwrap().unwrap();
+        assert_eq!(saved.sampling.cfg_scale, 4.0);
+        assert_eq!(saved.sampling.steps, 50);
+        assert!(saved.inherited_fields.is_empty());
+    }
+
+    #[test]
+    fn overrides_equal_to_parent_survive_parent_changes() {
+        let conn = open_in_memory().unwrap();
+        let mut parent = test_preset("parent");
+        parent.sampling.steps = 30;
+        parent.sampling.cfg_scale = 7.0;
+        insert_preset_row(&conn, &parent).unwrap();
+
+        let mut child = PresetConfig {
+            parent_id: Some(parent.id.clone()),
+            ..test_preset("child")
+        };
+        child.sampling.steps = 40;
+        child.sampling.cfg_scale = 7.0;
+        prepare_child(&conn, &mut child).unwrap();
+        insert_preset_row(&conn, &child).unwrap();
+        assert!(!child.inherited_fields.contains(&"sampling.steps".to_string()));
+
+        // The user pins steps to the parent's value.
+        let mut edited = load_preset(&conn, &child.id).unwrap().unwrap();
+        edited.sampling.steps = 30;
+        prepare_child(&conn, &mut edited).unwrap();
+        update_preset_row(&conn, &edited).unwrap();
+
+        parent.sampling.steps = 50;
+        parent.sampling.cfg_scale = 5.0;
+        update_preset_row(&conn, &parent).unwrap();
+        refresh_children(&conn, &parent, NOW).unwrap();
+        let saved = load_preset(&conn, &child.id).unwrap().unwrap();
+        assert_eq!(saved.sampling.steps, 30);
+        assert_eq!(saved.sampling.cfg_scale, 5.0);
+
+        // Clearing the override makes it follow the parent again.
+        let mut cleared = saved.clone();
+        cleared.inherited_fields.push("sampling.steps".to_string());
+        prepare_child(&conn, &mut cleared).unwrap();
+        assert_eq!(cleared.sampling.steps, 50);
+        assert!(cleared.inherited_fields.contains(&"sampling.steps".to_string()));
+    }
+}
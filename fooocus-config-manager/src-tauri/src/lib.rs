@@ -1,5 +1,25 @@
 mod database;
 mod commands;
+mod bulk;
+mod collections;
+mod diff;
+mod downloads;
+mod embeddings;
+mod families;
+mod gallery;
+mod inheritance;
+mod linking;
+mod lint;
+mod merge;
+mod metadata;
+mod model_files;
+mod outcomes;
+mod resources;
+mod revisions;
+mod stats;
+mod sweep;
+mod thumbnails;
+mod trash;
 
 use database::Database;
 use tauri::Manager;
@@ -15,6 +35,9 @@ pub fn run() {
             let db = Database::new(app_data_dir)
                 .expect("Failed to initialize database");
             app.manage(db);
+
+            let app_handle = app_handle.clone();
+            tauri::async_runtime::spawn_blocking(move || commands::purge_expired_trash(&app_handle));
             Ok(())
         })
         .invoke_handler(tauri::generate_handler![
@@ -23,21 +46,86 @@ pub fn run() {
             commands::create_preset,
             commands::update_preset,
             commands::delete_preset,
+            commands::duplicate_preset,
+            commands::get_preset_forks,
             commands::search_presets,
             commands::get_all_tags,
             commands::create_tag,
             commands::delete_tag,
             commands::toggle_favorite,
             commands::increment_use_count,
+            commands::set_preset_rating,
+            commands::set_preset_notes,
+            commands::record_preset_outcome,
+            commands::get_preset_outcomes,
+            commands::delete_preset_outcome,
+            commands::get_outcome_summaries,
+            commands::get_top_rated_presets,
             commands::get_all_models,
             commands::get_models_by_type,
             commands::get_model_by_id,
             commands::create_model,
             commands::update_model,
             commands::delete_model,
+            commands::undo_model_deletion,
             commands::search_models,
             commands::get_presets_by_model_id,
             commands::check_model_usage,
+            commands::relink_preset_models,
+            commands::link_preset_model,
+            commands::find_duplicate_models,
+            commands::merge_duplicate_models,
+            commands::import_model_hints,
+            commands::lint_preset,
+            commands::preview_sidecar_import,
+            commands::apply_sidecar_import,
+            commands::set_model_preview,
+            commands::clear_model_preview,
+            commands::import_model_previews,
+            commands::get_model_thumbnail,
+            commands::add_preset_images,
+            commands::get_preset_images,
+            commands::update_preset_image,
+            commands::set_preset_cover,
+            commands::remove_preset_image,
+            commands::get_preset_image_thumbnail,
+            commands::get_preset_cover_thumbnail,
+            commands::get_fooocus_paths,
+            commands::set_fooocus_paths,
+            commands::download_resources,
+            commands::get_resource_issues,
+            commands::clear_resource_issues,
+            commands::get_library_stats,
+            commands::get_model_families,
+            commands::create_model_family,
+            commands::update_model_family,
+            commands::delete_model_family,
+            commands::preview_family_upgrade,
+            commands::upgrade_family_presets,
+            commands::bulk_update_presets,
+            commands::generate_preset_sweep,
+            commands::get_collections,
+            commands::create_collection,
+            commands::update_collection,
+            commands::delete_collection,
+            commands::move_collection,
+            commands::add_presets_to_collection,
+            commands::remove_presets_from_collection,
+            commands::move_presets_to_collection,
+            commands::reorder_collection_presets,
+            commands::export_collection,
+            commands::get_preset_revisions,
+            commands::get_preset_revision,
+            commands::diff_preset_revisions,
+            commands::diff_presets,
+            commands::merge_presets,
+            commands::restore_preset_revision,
+            commands::get_trash,
+            commands::restore_from_trash,
+            commands::purge_trash,
+            commands::get_trash_purge_error,
+            commands::get_trash_retention_days,
+            commands::set_trash_retention_days,
         ])
         .run(tauri::generate_context!())
         .expect("error while running tauri application");
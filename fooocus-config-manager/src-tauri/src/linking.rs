@@ -0,0 +1,462 @@
+use crate::database::{load_all_models, load_all_presets, LoRA, ModelConfig, ModelInfo, ModelType, PresetConfig, PresetSummary};
+use crate::revisions;
+use chrono::Utc;
+use rusqlite::{Connection, Result as SqliteResult};
+use serde::{Deserialize, Serialize};
+
+pub const MODEL_EXTENSIONS: &[&str] = &["safetensors", "ckpt", "pt", "pth", "bin"];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum MatchStrategy {
+    Exact,
+    CaseInsensitive,
+    ExtensionInsensitive,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "camelCase")]
+pub enum ModelSlot {
+    Base,
+    Refiner,
+    Lora { index: usize },
+    Vae,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ModelCandidate {
+    pub id: String,
+    pub name: String,
+    pub file_name: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LinkedReference {
+    pub preset_id: String,
+    pub preset_name: String,
+    pub slot: ModelSlot,
+    pub model_name: String,
+    pub model_id: String,
+    pub strategy: MatchStrategy,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AmbiguousReference {
+    pub preset_id: String,
+    pub preset_name: String,
+    pub slot: ModelSlot,
+    pub model_name: String,
+    pub strategy: MatchStrategy,
+    pub candidates: Vec<ModelCandidate>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UnresolvedReference {
+    pub preset_id: String,
+    pub preset_name: String,
+    pub slot: ModelSlot,
+    pub model_name: String,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RelinkReport {
+    pub linked: Vec<LinkedReference>,
+    pub ambiguous: Vec<AmbiguousReference>,
+    pub unresolved: Vec<UnresolvedReference>,
+    pub updated_presets: i32,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "camelCase", rename_all_fields = "camelCase")]
+pub enum DeleteStrategy {
+    #[default]
+    Refuse,
+    Detach,
+    Replace { replacement_id: String },
+    RemoveLora,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ModelReference {
+    pub name: String,
+    pub model_id: Option<String>,
+}
+
+/// One reference a model deletion rewrote, so it can be reverted on its own.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "camelCase", rename_all_fields = "camelCase")]
+pub enum ReferenceChange {
+    /// A base, refiner or VAE reference.
+    Model { slot: ModelSlot, before: ModelReference, after: ModelReference },
+    /// A LoRA entry; `after` is `None` when it was removed.
+    Lora { index: usize, before: LoRA, after: Option<LoRA> },
+    /// An `embedding:` token in the positive or negative prompt.
+    Embedding { negative: bool, before: String, after: String },
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PresetReferenceChanges {
+    pub preset_id: String,
+    /// Empty in undo records written before changes were tracked.
+    #[serde(default)]
+    pub changes: Vec<ReferenceChange>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ModelDeletionUndo {
+    pub model: ModelInfo,
+    pub presets: Vec<PresetReferenceChanges>,
+}
+
+pub enum MatchOutcome<'a> {
+    Linked(&'a ModelInfo, MatchStrategy),
+    Ambiguous(Vec<&'a ModelInfo>, MatchStrategy),
+    NotFound,
+}
+
+impl MatchStrategy {
+    const ALL: [MatchStrategy; 3] = [
+        MatchStrategy::Exact,
+        MatchStrategy::CaseInsensitive,
+        MatchStrategy::ExtensionInsensitive,
+    ];
+
+    fn matches(self, file_name: &str, name: &str) -> bool {
+        match self {
+            MatchStrategy::Exact => file_name == name,
+            MatchStrategy::CaseInsensitive => file_name.to_lowercase() == name.to_lowercase(),
+            MatchStrategy::ExtensionInsensitive => {
+                strip_model_extension(file_name).to_lowercase() == strip_model_extension(name).to_lowercase()
+            }
+        }
+    }
+}
+
+impl ModelSlot {
+    pub fn accepts(&self, model_type: ModelType) -> bool {
+        match self {
+            ModelSlot::Base | ModelSlot::Refiner => {
+                matches!(model_type, ModelType::Checkpoint | ModelType::Refiner)
+            }
+            ModelSlot::Lora { .. } => model_type == ModelType::LoRA,
+            ModelSlot::Vae => model_type == ModelType::VAE,
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            ModelSlot::Base => "base model",
+            ModelSlot::Refiner => "refiner",
+            ModelSlot::Lora { .. } => "LoRA",
+            ModelSlot::Vae => "VAE",
+        }
+    }
+
+    /// The name and id fields of a base, refiner or VAE reference.
+    fn fields<'a>(&self, model: &'a mut ModelConfig) -> Option<(&'a mut String, &'a mut Option<String>)> {
+        match self {
+            ModelSlot::Base => Some((&mut model.base_model, &mut model.base_model_id)),
+            ModelSlot::Refiner => Some((&mut model.refiner_model, &mut model.refiner_model_id)),
+            ModelSlot::Vae => Some((&mut model.vae, &mut model.vae_model_id)),
+            ModelSlot::Lora { .. } => None,
+        }
+    }
+}
+
+/// The slots of `model` that reference `model_id`.
+pub fn slots_using(model: &ModelConfig, model_id: &str) -> Vec<ModelSlot> {
+    let is_target = |id: &Option<String>| id.as_deref() == Some(model_id);
+    let mut slots = Vec::new();
+    if is_target(&model.base_model_id) {
+        slots.push(ModelSlot::Base);
+    }
+    if is_target(&model.refiner_model_id) {
+        slots.push(ModelSlot::Refiner);
+    }
+    if is_target(&model.vae_model_id) {
+        slots.push(ModelSlot::Vae);
+    }
+    slots.extend(model.loras.iter().enumerate()
+        .filter(|(_, l)| is_target(&l.model_id))
+        .map(|(index, _)| ModelSlot::Lora { index }));
+    slots
+}
+
+pub fn strip_model_extension(name: &str) -> &str {
+    match name.rsplit_once('.') {
+        Some((stem, ext)) if MODEL_EXTENSIONS.iter().any(|e| ext.eq_ignore_ascii_case(e)) => stem,
+        _ => name,
+    }
+}
+
+/// Fooocus writes `Default (model)` for "use the checkpoint's own VAE".
+pub const DEFAULT_VAE: &str = "Default (model)";
+
+fn is_empty_reference(name: &str) -> bool {
+    let name = name.trim();
+    name.is_empty() || name.eq_ignore_ascii_case("None") || name.eq_ignore_ascii_case(DEFAULT_VAE)
+}
+
+pub fn match_model<'a>(name: &str, slot: &ModelSlot, models: &'a [ModelInfo]) -> MatchOutcome<'a> {
+    let name = name.trim();
+    let candidates: Vec<&ModelInfo> = models.iter().filter(|m| slot.accepts(m.model_type)).collect();
+
+    for strategy in MatchStrategy::ALL {
+        let found: Vec<&ModelInfo> = candidates.iter()
+            .copied()
+            .filter(|m| strategy.matches(&m.file_name, name))
+            .collect();
+        match found.len() {
+            0 => continue,
+            1 => return MatchOutcome::Linked(found[0], strategy),
+            _ => return MatchOutcome::Ambiguous(found, strategy),
+        }
+    }
+
+    MatchOutcome::NotFound
+}
+
+fn link_reference(
+    preset_id: &str,
+    preset_name: &str,
+    slot: ModelSlot,
+    model_name: &str,
+    model_id: &mut Option<String>,
+    models: &[ModelInfo],
+    report: &mut RelinkReport,
+) -> bool {
+    if let Some(id) = model_id.as_deref() {
+        if models.iter().any(|m| m.id == id) {
+            return false;
+        }
+    }
+    let had_dangling_id = model_id.take().is_some();
+
+    if is_empty_reference(model_name) {
+        return had_dangling_id;
+    }
+
+    match match_model(model_name, &slot, models) {
+        MatchOutcome::Linked(model, strategy) => {
+            *model_id = Some(model.id.clone());
+            report.linked.push(LinkedReference {
+                preset_id: preset_id.to_string(),
+                preset_name: preset_name.to_string(),
+                slot,
+                model_name: model_name.to_string(),
+                model_id: model.id.clone(),
+                strategy,
+            });
+            true
+        }
+        MatchOutcome::Ambiguous(found, strategy) => {
+            report.ambiguous.push(AmbiguousReference {
+                preset_id: preset_id.to_string(),
+                preset_name: preset_name.to_string(),
+                slot,
+                model_name: model_name.to_string(),
+                strategy,
+                candidates: found.into_iter().map(|m| ModelCandidate {
+                    id: m.id.clone(),
+                    name: m.name.clone(),
+                    file_name: m.file_name.clone(),
+                }).collect(),
+            });
+            had_dangling_id
+        }
+        MatchOutcome::NotFound => {
+            report.unresolved.push(UnresolvedReference {
+                preset_id: preset_id.to_string(),
+                preset_name: preset_name.to_string(),
+                slot,
+                model_name: model_name.to_string(),
+            });
+            had_dangling_id
+        }
+    }
+}
+
+/// Fills in missing or dangling model ids of a preset from its model names.
+/// Returns whether the preset's model config changed.
+pub fn link_preset(preset: &mut PresetConfig, models: &[ModelInfo], report: &mut RelinkReport) -> bool {
+    let (id, name) = (preset.id.clone(), preset.name.clone());
+    let model = &mut preset.model;
+    let mut changed = false;
+
+    changed |= link_reference(&id, &name, ModelSlot::Base, &model.base_model, &mut model.base_model_id, models, report);
+    changed |= link_reference(&id, &name, ModelSlot::Refiner, &model.refiner_model, &mut model.refiner_model_id, models, report);
+    changed |= link_reference(&id, &name, ModelSlot::Vae, &model.vae, &mut model.vae_model_id, models, report);
+    for (index, lora) in model.loras.iter_mut().enumerate() {
+        changed |= link_reference(&id, &name, ModelSlot::Lora { index }, &lora.model_name, &mut lora.model_id, models, report);
+    }
+
+    changed
+}
+
+pub fn relink_all_presets(conn: &mut Connection) -> SqliteResult<RelinkReport> {
+    let tx = conn.transaction()?;
+    let now = Utc::now().to_rfc3339();
+    let models = load_all_models(&tx)?;
+    let presets = load_all_presets(&tx)?;
+    let mut report = RelinkReport::default();
+
+    for mut preset in presets {
+        if link_preset(&mut preset, &models, &mut report) {
+            revisions::save_preset(&tx, &preset, &now)?;
+            report.updated_presets += 1;
+        }
+    }
+
+    tx.commit()?;
+    Ok(report)
+}
+
+/// Rewrites the model names of every preset linked to `model_id` so they follow
+/// the model's new file name. Runs inside the caller's transaction.
+pub fn rename_model_references(
+    conn: &Connection,
+    model_id: &str,
+    new_file_name: &str,
+    now: &str,
+) -> SqliteResult<Vec<PresetSummary>> {
+    let mut touched = Vec::new();
+
+    for mut preset in load_all_presets(conn)? {
+        let model = &mut preset.model;
+        let mut changed = false;
+
+        if model.base_model_id.as_deref() == Some(model_id) && model.base_model != new_file_name {
+            model.base_model = new_file_name.to_string();
+            changed = true;
+        }
+        if model.refiner_model_id.as_deref() == Some(model_id) && model.refiner_model != new_file_name {
+            model.refiner_model = new_file_name.to_string();
+            changed = true;
+        }
+        if model.vae_model_id.as_deref() == Some(model_id) && model.vae != new_file_name {
+            model.vae = new_file_name.to_string();
+            changed = true;
+        }
+        for lora in model.loras.iter_mut() {
+            if lora.model_id.as_deref() == Some(model_id) && lora.model_name != new_file_name {
+                lora.model_name = new_file_name.to_string();
+                changed = true;
+            }
+        }
+
+        if changed {
+            preset.updated_at = now.to_string();
+            revisions::save_preset(conn, &preset, now)?;
+            touched.push(PresetSummary {
+                id: preset.id,
+                name: preset.name,
+            });
+        }
+    }
+
+    Ok(touched)
+}
+
+/// Rewrites the references to a model that is about to be deleted and returns
+/// what changed. `RemoveLora` drops LoRA entries; base and refiner references
+/// are detached.
+pub fn apply_delete_strategy(
+    model: &mut ModelConfig,
+    model_id: &str,
+    strategy: &DeleteStrategy,
+    replacement: Option<&ModelInfo>,
+) -> Vec<ReferenceChange> {
+    let is_target = |id: &Option<String>| id.as_deref() == Some(model_id);
+    let replacement = match strategy {
+        DeleteStrategy::Replace { .. } => replacement,
+        _ => None,
+    };
+    let mut changes = Vec::new();
+
+    for slot in [ModelSlot::Base, ModelSlot::Refiner, ModelSlot::Vae] {
+        let Some((name, id)) = slot.fields(model) else { continue };
+        if !is_target(id) {
+            continue;
+        }
+        let before = ModelReference { name: name.clone(), model_id: id.clone() };
+        match replacement {
+            Some(replacement) => {
+                *name = replacement.file_name.clone();
+                *id = Some(replacement.id.clone());
+            }
+            None => *id = None,
+        }
+        let after = ModelReference { name: name.clone(), model_id: id.clone() };
+        changes.push(ReferenceChange::Model { slot, before, after });
+    }
+
+    let mut loras = Vec::new();
+    for (index, lora) in std::mem::take(&mut model.loras).into_iter().enumerate() {
+        if !is_target(&lora.model_id) {
+            loras.push(lora);
+            continue;
+        }
+        let after = match replacement {
+            Some(replacement) => Some(LoRA {
+                model_name: replacement.file_name.clone(),
+                model_id: Some(replacement.id.clone()),
+                ..lora.clone()
+            }),
+            None if *strategy == DeleteStrategy::RemoveLora => None,
+            None => Some(LoRA { model_id: None, ..lora.clone() }),
+        };
+        loras.extend(after.clone());
+        changes.push(ReferenceChange::Lora { index, before: lora, after });
+    }
+    model.loras = loras;
+
+    changes
+}
+
+/// Reverts the changes of `apply_delete_strategy` and
+/// `embeddings::replace_embedding`. References that were edited since are left
+/// alone. Returns whether anything was reverted.
+pub fn revert_reference_changes(preset: &mut PresetConfig, changes: &[ReferenceChange]) -> bool {
+    let model = &mut preset.model;
+    let mut reverted = false;
+    for change in changes {
+        match change {
+            ReferenceChange::Model { slot, before, after } => {
+                let Some((name, id)) = slot.fields(model) else { continue };
+                if *name == after.name && *id == after.model_id {
+                    *name = before.name.clone();
+                    *id = before.model_id.clone();
+                    reverted = true;
+                }
+            }
+            ReferenceChange::Lora { index, before, after: Some(after) } => {
+                let current = match model.loras.get(*index) {
+                    Some(lora) if lora == after => Some(*index),
+                    _ => model.loras.iter().position(|l| l == after),
+                };
+                if let Some(current) = current {
+                    model.loras[current] = before.clone();
+                    reverted = true;
+                }
+            }
+            ReferenceChange::Lora { index, before, after: None } => {
+                if !model.loras.iter().any(|l| l.model_name == before.model_name) {
+                    model.loras.insert((*index).min(model.loras.len()), before.clone());
+                    reverted = true;
+                }
+            }
+            ReferenceChange::Embedding { negative, before, after } => {
+                reverted |= crate::embeddings::rename_embedding(&mut preset.prompt, *negative, after, before);
+            }
+        }
+    }
+    reverted
+}
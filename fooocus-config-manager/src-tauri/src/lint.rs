@@ -0,0 +1,113 @@
+use crate::database::{ModelInfo, PresetConfig};
+use crate::embeddings;
+use crate::linking::{self, MatchOutcome, ModelSlot};
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "camelCase", rename_all_fields = "camelCase")]
+pub enum LintWarning {
+    MissingTriggerWords {
+        lora_index: usize,
+        model_id: String,
+        model_name: String,
+        trigger_words: Vec<String>,
+    },
+    WeightOutOfRange {
+        lora_index: usize,
+        model_id: String,
+        model_name: String,
+        weight: f64,
+        min: Option<f64>,
+        max: Option<f64>,
+    },
+    MissingEmbedding {
+        name: String,
+        negative: bool,
+    },
+    IncompatibleVae {
+        model_id: String,
+        model_name: String,
+        vae_base_model: String,
+        checkpoint_base_model: String,
+    },
+}
+
+fn find_model<'a>(model_id: Option<&str>, model_name: &str, slot: &ModelSlot, models: &'a [ModelInfo]) -> Option<&'a ModelInfo> {
+    if let Some(id) = model_id {
+        return models.iter().find(|m| m.id == id);
+    }
+    match linking::match_model(model_name, slot, models) {
+        MatchOutcome::Linked(model, _) => Some(model),
+        _ => None,
+    }
+}
+
+fn known_base_model(model: &ModelInfo) -> Option<&str> {
+    model.recommended_base_model.as_deref()
+        .map(str::trim)
+        .filter(|b| !b.is_empty())
+}
+
+/// A VAE built for another architecture than the checkpoint, e.g. an SD 1.5
+/// VAE on an SDXL checkpoint. Only reported when both base models are known.
+fn check_vae(preset: &PresetConfig, models: &[ModelInfo]) -> Option<LintWarning> {
+    let model = &preset.model;
+    let vae = find_model(model.vae_model_id.as_deref(), &model.vae, &ModelSlot::Vae, models)?;
+    let checkpoint = find_model(model.base_model_id.as_deref(), &model.base_model, &ModelSlot::Base, models)?;
+    let (vae_base, checkpoint_base) = (known_base_model(vae)?, known_base_model(checkpoint)?);
+
+    (!vae_base.eq_ignore_ascii_case(checkpoint_base)).then(|| LintWarning::IncompatibleVae {
+        model_id: vae.id.clone(),
+        model_name: model.vae.clone(),
+        vae_base_model: vae_base.to_string(),
+        checkpoint_base_model: checkpoint_base.to_string(),
+    })
+}
+
+pub fn lint_preset(preset: &PresetConfig, models: &[ModelInfo]) -> Vec<LintWarning> {
+    let mut warnings = Vec::new();
+    let positive = preset.prompt.positive.to_lowercase();
+
+    for (index, lora) in preset.model.loras.iter().enumerate() {
+        let Some(model) = find_model(lora.model_id.as_deref(), &lora.model_name, &ModelSlot::Lora { index }, models) else {
+            continue;
+        };
+
+        let has_trigger_word = model.trigger_words.iter()
+            .any(|w| positive.contains(&w.to_lowercase()));
+        if !model.trigger_words.is_empty() && !has_trigger_word {
+            warnings.push(LintWarning::MissingTriggerWords {
+                lora_index: index,
+                model_id: model.id.clone(),
+                model_name: lora.model_name.clone(),
+                trigger_words: model.trigger_words.clone(),
+            });
+        }
+
+        let below = model.recommended_weight_min.is_some_and(|min| lora.weight < min);
+        let above = model.recommended_weight_max.is_some_and(|max| lora.weight > max);
+        if below || above {
+            warnings.push(LintWarning::WeightOutOfRange {
+                lora_index: index,
+                model_id: model.id.clone(),
+                model_name: lora.model_name.clone(),
+                weight: lora.weight,
+                min: model.recommended_weight_min,
+                max: model.recommended_weight_max,
+            });
+        }
+    }
+
+    for reference in embeddings::prompt_embeddings(&preset.prompt) {
+        if embeddings::resolve_embedding(&reference.name, models).is_none() {
+            warnings.push(LintWarning::MissingEmbedding {
+                name: reference.name,
+                negative: reference.negative,
+            });
+        }
+    }
+
+    warnings.extend(check_vae(preset, models));
+
+    warnings
+}
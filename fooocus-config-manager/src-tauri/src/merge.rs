@@ -0,0 +1,226 @@
+use crate::database::PresetConfig;
+use crate::diff;
+use crate::revisions;
+use rusqlite::Connection;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::collections::HashMap;
+
+/// Order-insensitive string lists, merged as sets.
+const SET_FIELDS: &[&str] = &["tags", "prompt.styles"];
+/// LoRA lists are merged entry by entry, matching entries by model.
+const LORAS_FIELD: &str = "model.loras";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum MergeSide {
+    Base,
+    Ours,
+    Theirs,
+}
+
+/// A field both sides changed to different values.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MergeConflict {
+    pub path: String,
+    pub base: Value,
+    pub ours: Value,
+    pub theirs: Value,
+    /// The side the merged preset takes; ours unless resolved otherwise.
+    pub resolution: MergeSide,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PresetMerge {
+    pub merged: PresetConfig,
+    pub conflicts: Vec<MergeConflict>,
+}
+
+fn lora_id(lora: &Value) -> Option<&str> {
+    lora.get("modelId").and_then(Value::as_str)
+}
+
+fn lora_name(lora: &Value) -> &str {
+    lora.get("modelName").and_then(Value::as_str).unwrap_or_default()
+}
+
+fn same_lora(a: &Value, b: &Value) -> bool {
+    match (lora_id(a), lora_id(b)) {
+        (Some(x), Some(y)) => x == y,
+        _ => lora_name(a).eq_ignore_ascii_case(lora_name(b)),
+    }
+}
+
+/// Keeps ours, adds what theirs added and drops what theirs removed.
+fn merge_set(base: &[Value], ours: &[Value], theirs: &[Value]) -> Value {
+    let mut merged: Vec<Value> = ours
+        .iter()
+        .filter(|item| !base.contains(item) || theirs.contains(item))
+        .cloned()
+        .collect();
+    merged.extend(theirs.iter().filter(|item| !base.contains(item) && !ours.contains(item)).cloned());
+    Value::Array(merged)
+}
+
+struct Merger<'a> {
+    resolutions: &'a HashMap<String, MergeSide>,
+    conflicts: Vec<MergeConflict>,
+}
+
+impl Merger<'_> {
+    fn conflict(&mut self, path: &str, base: &Value, ours: &Value, theirs: &Value) -> Value {
+        let resolution = self.resolutions.get(path).copied().unwrap_or(MergeSide::Ours);
+        self.conflicts.push(MergeConflict {
+            path: path.to_string(),
+            base: base.clone(),
+            ours: ours.clone(),
+            theirs: theirs.clone(),
+            resolution,
+        });
+        match resolution {
+            MergeSide::Base => base.clone(),
+            MergeSide::Ours => ours.clone(),
+            MergeSide::Theirs => theirs.clone(),
+        }
+    }
+
+    fn merge(&mut self, path: &str, base: &Value, ours: &Value, theirs: &Value) -> Value {
+        if ours == theirs || theirs == base {
+            return ours.clone();
+        }
+        if ours == base {
+            return theirs.clone();
+        }
+
+        // A field the base lacks merges as if it were empty.
+        let empty_list = Vec::new();
+        let base_list = if base.is_null() { Some(&empty_list) } else { base.as_array() };
+        if let (Some(b), Some(o), Some(t)) = (base_list, ours.as_array(), theirs.as_array()) {
+            if SET_FIELDS.contains(&path) {
+                return merge_set(b, o, t);
+            }
+            if path == LORAS_FIELD {
+                return self.merge_loras(b, o, t);
+            }
+        }
+
+        let empty_object = Map::new();
+        let base_object = if base.is_null() { Some(&empty_object) } else { base.as_object() };
+        match (base_object, ours.as_object(), theirs.as_object()) {
+            (Some(b), Some(o), Some(t)) => Value::Object(self.merge_objects(path, b, o, t)),
+            _ => self.conflict(path, base, ours, theirs),
+        }
+    }
+
+    fn merge_objects(&mut self, path: &str, base: &Map<String, Value>, ours: &Map<String, Value>, theirs: &Map<String, Value>) -> Map<String, Value> {
+        let mut keys: Vec<&String> = ours.keys().collect();
+        for key in theirs.keys().chain(base.keys()) {
+            if !keys.contains(&key) {
+                keys.push(key);
+            }
+        }
+
+        let mut merged = Map::new();
+        for key in keys {
+            let child = if path.is_empty() { key.clone() } else { format!("{}.{}", path, key) };
+            let value = self.merge(
+                &child,
+                base.get(key).unwrap_or(&Value::Null),
+                ours.get(key).unwrap_or(&Value::Null),
+                theirs.get(key).unwrap_or(&Value::Null),
+            );
+            // Absent optional fields serialize as missing keys, not nulls.
+            if !value.is_null() {
+                merged.insert(key.clone(), value);
+            }
+        }
+        merged
+    }
+
+    /// Merges each LoRA across the three lists, so one side reweighting a LoRA
+    /// and the other adding a different one do not conflict.
+    fn merge_loras(&mut self, base: &[Value], ours: &[Value], theirs: &[Value]) -> Value {
+        let mut entries: Vec<&Value> = Vec::new();
+        for lora in ours.iter().chain(theirs).chain(base) {
+            if !entries.iter().any(|e| same_lora(e, lora)) {
+                entries.push(lora);
+            }
+        }
+
+        let find = |list: &[Value], entry: &Value| list.iter().find(|l| same_lora(l, entry)).cloned().unwrap_or(Value::Null);
+        let mut merged = Vec::new();
+        for entry in entries {
+            let path = format!("{}[{}]", LORAS_FIELD, lora_name(entry));
+            let value = self.merge(&path, &find(base, entry), &find(ours, entry), &find(theirs, entry));
+            if !value.is_null() {
+                merged.push(value);
+            }
+        }
+        Value::Array(merged)
+    }
+}
+
+/// Merges the changes `ours` and `theirs` made since `base`. Conflicting
+/// fields take the side given in `resolutions` for their path, or ours.
+/// The result keeps the name, identity and bookkeeping fields of `ours`.
+pub fn merge_presets(
+    base: &PresetConfig,
+    ours: &PresetConfig,
+    theirs: &PresetConfig,
+    resolutions: &HashMap<String, MergeSide>,
+) -> Result<PresetMerge, String> {
+    let renamed = |preset: &PresetConfig| PresetConfig {
+        name: ours.name.clone(),
+        ..preset.clone()
+    };
+    let mut merger = Merger {
+        resolutions,
+        conflicts: Vec::new(),
+    };
+    let merged = merger.merge(
+        "",
+        &diff::settings_value(&renamed(base))?,
+        &diff::settings_value(ours)?,
+        &diff::settings_value(&renamed(theirs))?,
+    );
+
+    let ours_settings = diff::settings_value(ours)?;
+    let mut full = serde_json::to_value(ours).map_err(|e| e.to_string())?;
+    if let (Some(full), Some(merged)) = (full.as_object_mut(), merged.as_object()) {
+        full.retain(|key, _| ours_settings.get(key).is_none());
+        full.extend(merged.clone());
+    }
+
+    Ok(PresetMerge {
+        merged: serde_json::from_value(full).map_err(|e| e.to_string())?,
+        conflicts: merger.conflicts,
+    })
+}
+
+/// The newest stored state of `ours_id` that `theirs_id` also passed through.
+/// Names are ignored, since a fork is usually renamed.
+pub fn find_common_ancestor(conn: &Connection, ours_id: &str, theirs_id: &str) -> Result<Option<PresetConfig>, String> {
+    let fingerprint = |preset: &PresetConfig| -> Result<Value, String> {
+        let mut value = diff::settings_value(preset)?;
+        if let Some(object) = value.as_object_mut() {
+            object.remove("name");
+        }
+        Ok(value)
+    };
+
+    let ours = revisions::load_snapshots(conn, ours_id).map_err(|e| e.to_string())?;
+    let theirs = revisions::load_snapshots(conn, theirs_id).map_err(|e| e.to_string())?;
+    let theirs: Vec<Value> = theirs
+        .iter()
+        .map(|(_, _, preset)| fingerprint(preset))
+        .collect::<Result<_, _>>()?;
+
+    for (_, _, preset) in ours {
+        if theirs.contains(&fingerprint(&preset)?) {
+            return Ok(Some(preset));
+        }
+    }
+    Ok(None)
+}
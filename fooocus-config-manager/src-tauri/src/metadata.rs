@@ -0,0 +1,311 @@
+use crate::database::ModelInfo;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+
+const MAX_SAFETENSORS_HEADER: u64 = 100 * 1024 * 1024;
+
+const SCOPE_KEYWORDS: &[(&str, &str)] = &[
+    ("portrait", "人像"),
+    ("photorealistic", "写实"),
+    ("realistic", "写实"),
+    ("anime", "动漫"),
+    ("landscape", "风景"),
+    ("detail", "细节增强"),
+    ("style", "风格化"),
+    ("art", "艺术"),
+    ("illustration", "插画"),
+];
+
+#[derive(Debug, Clone, Default)]
+pub struct ModelHints {
+    pub trigger_words: Vec<String>,
+    pub weight_min: Option<f64>,
+    pub weight_max: Option<f64>,
+    pub base_model: Option<String>,
+}
+
+/// Reads the `__metadata__` map from a safetensors header without loading the tensors.
+pub fn read_safetensors_metadata(path: &Path) -> std::io::Result<HashMap<String, String>> {
+    let mut file = File::open(path)?;
+    let mut len_bytes = [0u8; 8];
+    file.read_exact(&mut len_bytes)?;
+    let header_len = u64::from_le_bytes(len_bytes);
+    if header_len > MAX_SAFETENSORS_HEADER {
+        return Err(std::io::Error::new(std::io::ErrorKind::InvalidData, "safetensors header too large"));
+    }
+
+    let mut header = vec![0u8; header_len as usize];
+    file.read_exact(&mut header)?;
+    let header: Value = serde_json::from_slice(&header)
+        .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))?;
+
+    Ok(header.get("__metadata__")
+        .and_then(|m| m.as_object())
+        .map(|m| m.iter()
+            .filter_map(|(k, v)| v.as_str().map(|v| (k.clone(), v.to_string())))
+            .collect())
+        .unwrap_or_default())
+}
+
+/// `models/foo.safetensors` + `.civitai.info` -> `models/foo.civitai.info`
+pub fn sidecar_path(model_file: &Path, suffix: &str) -> PathBuf {
+    let stem = model_file.file_stem().map(|s| s.to_string_lossy().to_string()).unwrap_or_default();
+    model_file.with_file_name(format!("{}{}", stem, suffix))
+}
+
+pub fn read_json_file(path: &Path) -> Option<Value> {
+    let text = std::fs::read_to_string(path).ok()?;
+    serde_json::from_str(&text).ok()
+}
+
+fn split_words(text: &str) -> Vec<String> {
+    text.split(',')
+        .map(|w| w.trim().to_string())
+        .filter(|w| !w.is_empty())
+        .collect()
+}
+
+pub fn hints_from_civitai(info: &Value) -> ModelHints {
+    let mut trigger_words: Vec<String> = Vec::new();
+    for word in info.get("trainedWords").and_then(|w| w.as_array()).into_iter().flatten() {
+        for word in word.as_str().map(split_words).unwrap_or_default() {
+            if !trigger_words.contains(&word) {
+                trigger_words.push(word);
+            }
+        }
+    }
+
+    let settings = info.get("settings").unwrap_or(info);
+    ModelHints {
+        trigger_words,
+        weight_min: settings.get("minStrength").and_then(|v| v.as_f64()),
+        weight_max: settings.get("maxStrength").and_then(|v| v.as_f64()),
+        base_model: info.get("baseModel").and_then(|v| v.as_str()).map(String::from),
+    }
+}
+
+pub fn hints_from_safetensors(metadata: &HashMap<String, String>) -> ModelHints {
+    ModelHints {
+        trigger_words: metadata.get("modelspec.trigger_phrase").map(|p| split_words(p)).unwrap_or_default(),
+        weight_min: None,
+        weight_max: None,
+        base_model: metadata.get("ss_base_model_version")
+            .or_else(|| metadata.get("modelspec.architecture"))
+            .cloned(),
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct SidecarMetadata {
+    pub files: Vec<String>,
+    pub name: Option<String>,
+    pub description: Option<String>,
+    pub tags: Vec<String>,
+    pub scope: Vec<String>,
+    pub source_url: Option<String>,
+    pub hints: ModelHints,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FieldChange {
+    pub field: String,
+    pub current: Value,
+    pub incoming: Value,
+    pub overwrites: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SidecarImportPreview {
+    pub model_id: String,
+    pub model_name: String,
+    pub files: Vec<String>,
+    pub changes: Vec<FieldChange>,
+}
+
+fn strip_html(text: &str) -> String {
+    let mut plain = String::with_capacity(text.len());
+    let mut in_tag = false;
+    for c in text.chars() {
+        match c {
+            '<' => in_tag = true,
+            '>' if in_tag => in_tag = false,
+            _ if !in_tag => plain.push(c),
+            _ => {}
+        }
+    }
+    plain.trim().to_string()
+}
+
+fn non_empty(value: Option<&Value>) -> Option<String> {
+    value.and_then(|v| v.as_str())
+        .map(|s| s.trim().to_string())
+        .filter(|s| !s.is_empty())
+}
+
+/// Reads the sidecars downloader extensions leave next to a model file:
+/// `<stem>.civitai.info` (CivitAI Helper) and `<stem>.json` (A1111 user metadata).
+pub fn read_sidecars(model_file: &Path) -> SidecarMetadata {
+    let mut meta = SidecarMetadata::default();
+
+    let civitai_path = sidecar_path(model_file, ".civitai.info");
+    if let Some(info) = read_json_file(&civitai_path) {
+        meta.files.push(civitai_path.to_string_lossy().to_string());
+        let model = info.get("model");
+
+        meta.name = non_empty(model.and_then(|m| m.get("name")));
+        meta.description = non_empty(model.and_then(|m| m.get("description")))
+            .or_else(|| non_empty(info.get("description")))
+            .map(|d| strip_html(&d));
+        meta.tags = model.and_then(|m| m.get("tags"))
+            .and_then(|t| t.as_array())
+            .map(|t| t.iter().filter_map(|t| t.as_str().map(String::from)).collect())
+            .unwrap_or_default();
+        meta.source_url = match (info.get("modelId").and_then(|v| v.as_i64()), info.get("id").and_then(|v| v.as_i64())) {
+            (Some(model_id), Some(version_id)) => {
+                Some(format!("https://civitai.com/models/{}?modelVersionId={}", model_id, version_id))
+            }
+            _ => non_empty(info.get("downloadUrl")),
+        };
+        meta.hints = hints_from_civitai(&info);
+    }
+
+    let user_path = sidecar_path(model_file, ".json");
+    if let Some(user) = read_json_file(&user_path) {
+        meta.files.push(user_path.to_string_lossy().to_string());
+        if meta.description.is_none() {
+            meta.description = non_empty(user.get("description")).or_else(|| non_empty(user.get("notes")));
+        }
+        if meta.hints.trigger_words.is_empty() {
+            meta.hints.trigger_words = non_empty(user.get("activation text"))
+                .map(|t| split_words(&t))
+                .unwrap_or_default();
+        }
+        if meta.hints.base_model.is_none() {
+            meta.hints.base_model = non_empty(user.get("sd version"));
+        }
+    }
+
+    for tag in &meta.tags {
+        let tag = tag.to_lowercase();
+        for (keyword, scope) in SCOPE_KEYWORDS {
+            if tag.contains(keyword) && !meta.scope.iter().any(|s| s == scope) {
+                meta.scope.push(scope.to_string());
+            }
+        }
+    }
+
+    meta
+}
+
+fn push_change(changes: &mut Vec<FieldChange>, field: &str, current: Value, incoming: Value, current_is_empty: bool) {
+    if incoming.is_null() || incoming == json!([]) || current == incoming {
+        return;
+    }
+    changes.push(FieldChange {
+        field: field.to_string(),
+        current,
+        incoming,
+        overwrites: !current_is_empty,
+    });
+}
+
+/// Lists the fields a sidecar import would change. `overwrites` marks fields the
+/// user has already filled in and that should only change after confirmation.
+pub fn diff_sidecar(model: &ModelInfo, meta: &SidecarMetadata) -> Vec<FieldChange> {
+    let mut changes = Vec::new();
+    push_change(&mut changes, "name", json!(model.name), json!(meta.name), model.name.is_empty() || model.name == model.file_name);
+    push_change(&mut changes, "description", json!(model.description), json!(meta.description), model.description.is_empty());
+    push_change(&mut changes, "tags", json!(model.tags), json!(meta.tags), model.tags.is_empty());
+    push_change(&mut changes, "scope", json!(model.scope), json!(meta.scope), model.scope.is_empty());
+    push_change(&mut changes, "triggerWords", json!(model.trigger_words), json!(meta.hints.trigger_words), model.trigger_words.is_empty());
+    push_change(&mut changes, "recommendedWeightMin", json!(model.recommended_weight_min), json!(meta.hints.weight_min), model.recommended_weight_min.is_none());
+    push_change(&mut changes, "recommendedWeightMax", json!(model.recommended_weight_max), json!(meta.hints.weight_max), model.recommended_weight_max.is_none());
+    push_change(&mut changes, "recommendedBaseModel", json!(model.recommended_base_model), json!(meta.hints.base_model), model.recommended_base_model.is_none());
+    push_change(&mut changes, "sourceUrl", json!(model.source_url), json!(meta.source_url), model.source_url.is_none());
+    changes
+}
+
+/// Applies the listed fields from the sidecar metadata to the model.
+pub fn apply_sidecar(model: &mut ModelInfo, meta: SidecarMetadata, fields: &[String]) {
+    let wants = |field: &str| fields.iter().any(|f| f == field);
+    if wants("name") {
+        if let Some(name) = meta.name {
+            model.name = name;
+        }
+    }
+    if wants("description") {
+        if let Some(description) = meta.description {
+            model.description = description;
+        }
+    }
+    if wants("tags") && !meta.tags.is_empty() {
+        model.tags = meta.tags;
+    }
+    if wants("scope") && !meta.scope.is_empty() {
+        model.scope = meta.scope;
+    }
+    if wants("triggerWords") && !meta.hints.trigger_words.is_empty() {
+        model.trigger_words = meta.hints.trigger_words;
+    }
+    if wants("recommendedWeightMin") && meta.hints.weight_min.is_some() {
+        model.recommended_weight_min = meta.hints.weight_min;
+    }
+    if wants("recommendedWeightMax") && meta.hints.weight_max.is_some() {
+        model.recommended_weight_max = meta.hints.weight_max;
+    }
+    if wants("recommendedBaseModel") && meta.hints.base_model.is_some() {
+        model.recommended_base_model = meta.hints.base_model;
+    }
+    if wants("sourceUrl") && meta.source_url.is_some() {
+        model.source_url = meta.source_url;
+    }
+}
+
+/// Collects hints for a model file, preferring its sidecars and falling back
+/// to the safetensors header for anything they leave out.
+pub fn collect_hints(model_file: &Path) -> ModelHints {
+    let mut hints = read_sidecars(model_file).hints;
+
+    let is_safetensors = model_file.extension()
+        .map(|e| e.eq_ignore_ascii_case("safetensors"))
+        .unwrap_or(false);
+    if is_safetensors {
+        if let Ok(metadata) = read_safetensors_metadata(model_file) {
+            let fallback = hints_from_safetensors(&metadata);
+            if hints.trigger_words.is_empty() {
+                hints.trigger_words = fallback.trigger_words;
+            }
+            hints.base_model = hints.base_model.or(fallback.base_model);
+        }
+    }
+
+    hints
+}
+
+/// Fills the hint fields of a model that are still empty; user-entered values win.
+pub fn apply_hints(model: &mut ModelInfo, hints: ModelHints) -> bool {
+    let mut changed = false;
+    if model.trigger_words.is_empty() && !hints.trigger_words.is_empty() {
+        model.trigger_words = hints.trigger_words;
+        changed = true;
+    }
+    if model.recommended_weight_min.is_none() && hints.weight_min.is_some() {
+        model.recommended_weight_min = hints.weight_min;
+        changed = true;
+    }
+    if model.recommended_weight_max.is_none() && hints.weight_max.is_some() {
+        model.recommended_weight_max = hints.weight_max;
+        changed = true;
+    }
+    if model.recommended_base_model.is_none() && hints.base_model.is_some() {
+        model.recommended_base_model = hints.base_model;
+        changed = true;
+    }
+    changed
+}
@@ -0,0 +1,152 @@
+use crate::database::{ModelInfo, PresetSummary};
+use crate::linking::{ModelCandidate, MODEL_EXTENSIONS};
+use rusqlite::{params, Connection, OptionalExtension, Result as SqliteResult};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::time::UNIX_EPOCH;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FileStamp {
+    pub size: u64,
+    pub modified: i64,
+}
+
+pub fn file_stamp(path: &Path) -> std::io::Result<FileStamp> {
+    let metadata = std::fs::metadata(path)?;
+    let modified = metadata.modified()?
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs() as i64)
+        .unwrap_or_default();
+    Ok(FileStamp {
+        size: metadata.len(),
+        modified,
+    })
+}
+
+/// Locates the file of a model on disk. `path` may point at the file itself or
+/// at the folder holding `file_name`; download links resolve to nothing.
+pub fn resolve_model_file(model: &ModelInfo) -> Option<PathBuf> {
+    let path = model.path.trim();
+    if path.is_empty() || path.contains("://") {
+        return None;
+    }
+
+    let path = PathBuf::from(path);
+    if path.is_file() {
+        return Some(path);
+    }
+    if path.is_dir() && !model.file_name.is_empty() {
+        let candidate = path.join(&model.file_name);
+        if candidate.is_file() {
+            return Some(candidate);
+        }
+    }
+    None
+}
+
+pub fn is_model_file(path: &Path) -> bool {
+    path.extension()
+        .and_then(|e| e.to_str())
+        .map(|e| MODEL_EXTENSIONS.iter().any(|m| e.eq_ignore_ascii_case(m)))
+        .unwrap_or(false)
+}
+
+/// Recursively collects model files below `dir`.
+pub fn scan_model_files(dir: &Path, found: &mut Vec<PathBuf>) -> std::io::Result<()> {
+    for entry in std::fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.is_dir() {
+            scan_model_files(&path, found)?;
+        } else if is_model_file(&path) {
+            found.push(path);
+        }
+    }
+    Ok(())
+}
+
+pub fn sha256_file(path: &Path) -> std::io::Result<String> {
+    let mut file = File::open(path)?;
+    let mut hasher = Sha256::new();
+    let mut buffer = vec![0u8; 1024 * 1024];
+    loop {
+        let read = file.read(&mut buffer)?;
+        if read == 0 {
+            break;
+        }
+        hasher.update(&buffer[..read]);
+    }
+    Ok(format!("{:x}", hasher.finalize()))
+}
+
+pub fn cached_hash(conn: &Connection, path: &Path, stamp: &FileStamp) -> SqliteResult<Option<String>> {
+    conn.query_row(
+        "SELECT hash FROM file_hashes WHERE path = ?1 AND size = ?2 AND modified = ?3",
+        params![path.to_string_lossy(), stamp.size as i64, stamp.modified],
+        |row| row.get(0),
+    ).optional()
+}
+
+pub fn store_hash(conn: &Connection, path: &Path, stamp: &FileStamp, hash: &str) -> SqliteResult<()> {
+    conn.execute(
+        "INSERT OR REPLACE INTO file_hashes (path, size, modified, hash) VALUES (?1, ?2, ?3, ?4)",
+        params![path.to_string_lossy(), stamp.size as i64, stamp.modified, hash],
+    )?;
+    Ok(())
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DuplicateModelEntry {
+    pub model: ModelInfo,
+    pub file_path: String,
+    pub presets: Vec<PresetSummary>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DuplicateGroup {
+    pub hash: String,
+    pub size: u64,
+    pub models: Vec<DuplicateModelEntry>,
+    pub unregistered_files: Vec<String>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DuplicateReport {
+    pub groups: Vec<DuplicateGroup>,
+    pub missing_files: Vec<ModelCandidate>,
+}
+
+/// Groups files by SHA-256, hashing only files whose size collides with another.
+pub fn group_by_content<F>(files: Vec<PathBuf>, mut hash: F) -> Vec<(String, u64, Vec<PathBuf>)>
+where
+    F: FnMut(&Path, &FileStamp) -> std::io::Result<String>,
+{
+    let mut by_size: HashMap<u64, Vec<(PathBuf, FileStamp)>> = HashMap::new();
+    for path in files {
+        if let Ok(stamp) = file_stamp(&path) {
+            by_size.entry(stamp.size).or_default().push((path, stamp));
+        }
+    }
+
+    let mut by_hash: HashMap<String, (u64, Vec<PathBuf>)> = HashMap::new();
+    for (size, entries) in by_size.into_iter().filter(|(_, e)| e.len() > 1) {
+        for (path, stamp) in entries {
+            if let Ok(digest) = hash(&path, &stamp) {
+                by_hash.entry(digest).or_insert_with(|| (size, Vec::new())).1.push(path);
+            }
+        }
+    }
+
+    let mut groups: Vec<(String, u64, Vec<PathBuf>)> = by_hash.into_iter()
+        .filter(|(_, (_, paths))| paths.len() > 1)
+        .map(|(digest, (size, paths))| (digest, size, paths))
+        .collect();
+    groups.sort_by_key(|g| std::cmp::Reverse(g.1));
+    groups
+}
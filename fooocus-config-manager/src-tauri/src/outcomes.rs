@@ -0,0 +1,163 @@
+use crate::database::PresetConfig;
+use rusqlite::types::{FromSql, FromSqlError, FromSqlResult, ToSql, ToSqlOutput, ValueRef};
+use rusqlite::{params, Connection, Result as SqliteResult, Row};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// What happened to the images of one use of a preset.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum Outcome {
+    Kept,
+    Discarded,
+}
+
+impl Outcome {
+    fn as_str(self) -> &'static str {
+        match self {
+            Outcome::Kept => "kept",
+            Outcome::Discarded => "discarded",
+        }
+    }
+}
+
+impl ToSql for Outcome {
+    fn to_sql(&self) -> SqliteResult<ToSqlOutput<'_>> {
+        Ok(ToSqlOutput::from(self.as_str()))
+    }
+}
+
+impl FromSql for Outcome {
+    fn column_result(value: ValueRef<'_>) -> FromSqlResult<Self> {
+        match value.as_str()? {
+            "kept" => Ok(Outcome::Kept),
+            "discarded" => Ok(Outcome::Discarded),
+            other => Err(FromSqlError::Other(format!("Unknown outcome: {}", other).into())),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PresetOutcome {
+    pub id: String,
+    pub preset_id: String,
+    pub outcome: Outcome,
+    pub comment: String,
+    pub used_at: String,
+}
+
+/// Outcome counts of one preset.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OutcomeSummary {
+    pub preset_id: String,
+    pub kept: u32,
+    pub discarded: u32,
+    pub last_used_at: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PresetRanking {
+    pub preset: PresetConfig,
+    pub kept: u32,
+    pub discarded: u32,
+}
+
+impl PresetRanking {
+    /// Share of recorded uses that were kept, if there are any.
+    fn keep_rate(&self) -> Option<f64> {
+        let total = self.kept + self.discarded;
+        (total > 0).then(|| self.kept as f64 / total as f64)
+    }
+}
+
+pub fn validate_rating(rating: Option<u8>) -> Result<(), String> {
+    match rating {
+        Some(rating) if !(1..=5).contains(&rating) => Err(format!("Rating must be between 1 and 5, got {}", rating)),
+        _ => Ok(()),
+    }
+}
+
+/// Parses an RFC 3339 timestamp and stores it in UTC, so outcomes sort by time.
+pub fn parse_used_at(used_at: &str) -> Result<String, String> {
+    chrono::DateTime::parse_from_rfc3339(used_at.trim())
+        .map(|t| t.with_timezone(&chrono::Utc).to_rfc3339())
+        .map_err(|e| format!("Invalid time {}: {}", used_at, e))
+}
+
+const OUTCOME_COLUMNS: &str = "id, preset_id, outcome, comment, used_at";
+
+impl PresetOutcome {
+    fn from_row(row: &Row) -> SqliteResult<Self> {
+        Ok(PresetOutcome {
+            id: row.get(0)?,
+            preset_id: row.get(1)?,
+            outcome: row.get(2)?,
+            comment: row.get::<_, Option<String>>(3)?.unwrap_or_default(),
+            used_at: row.get(4)?,
+        })
+    }
+}
+
+/// Outcomes of a preset, newest first.
+pub fn load_outcomes(conn: &Connection, preset_id: &str) -> SqliteResult<Vec<PresetOutcome>> {
+    let mut stmt = conn.prepare(&format!(
+        "SELECT {} FROM preset_outcomes WHERE preset_id = ?1 ORDER BY used_at DESC",
+        OUTCOME_COLUMNS
+    ))?;
+    let outcomes = stmt.query_map(params![preset_id], PresetOutcome::from_row)?;
+    outcomes.collect()
+}
+
+pub fn insert_outcome(conn: &Connection, outcome: &PresetOutcome) -> SqliteResult<()> {
+    conn.execute(
+        "INSERT INTO preset_outcomes (id, preset_id, outcome, comment, used_at) VALUES (?1, ?2, ?3, ?4, ?5)",
+        params![outcome.id, outcome.preset_id, outcome.outcome, outcome.comment, outcome.used_at],
+    )?;
+    Ok(())
+}
+
+/// Outcome counts for every preset that has any.
+pub fn load_summaries(conn: &Connection) -> SqliteResult<Vec<OutcomeSummary>> {
+    let mut stmt = conn.prepare(
+        "SELECT preset_id,
+                SUM(CASE WHEN outcome = 'kept' THEN 1 ELSE 0 END),
+                SUM(CASE WHEN outcome = 'discarded' THEN 1 ELSE 0 END),
+                MAX(used_at)
+         FROM preset_outcomes GROUP BY preset_id"
+    )?;
+    let summaries = stmt.query_map([], |row| {
+        Ok(OutcomeSummary {
+            preset_id: row.get(0)?,
+            kept: row.get(1)?,
+            discarded: row.get(2)?,
+            last_used_at: row.get(3)?,
+        })
+    })?;
+    summaries.collect()
+}
+
+/// Orders presets by rating, unrated last, then by how often their results
+/// were kept, then by use count.
+pub fn rank(presets: Vec<PresetConfig>, summaries: &[OutcomeSummary]) -> Vec<PresetRanking> {
+    let summaries: HashMap<&str, &OutcomeSummary> = summaries.iter().map(|s| (s.preset_id.as_str(), s)).collect();
+    let mut rankings: Vec<PresetRanking> = presets.into_iter()
+        .map(|preset| {
+            let summary = summaries.get(preset.id.as_str());
+            PresetRanking {
+                kept: summary.map_or(0, |s| s.kept),
+                discarded: summary.map_or(0, |s| s.discarded),
+                preset,
+            }
+        })
+        .collect();
+
+    rankings.sort_by(|a, b| {
+        b.preset.rating.cmp(&a.preset.rating)
+            .then_with(|| b.keep_rate().partial_cmp(&a.keep_rate()).unwrap_or(std::cmp::Ordering::Equal))
+            .then_with(|| b.preset.use_count.cmp(&a.preset.use_count))
+    });
+    rankings
+}
@@ -0,0 +1,230 @@
+use crate::database::{get_setting, set_setting, ResourceDownloads, ResourceEntry, ResourceMap};
+use crate::downloads::DownloadKind;
+use crate::model_files::is_model_file;
+use rusqlite::{params, Connection, Result as SqliteResult};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::path::Path;
+
+const MIGRATED_SETTING: &str = "typed_resources_migrated";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ResourceIssue {
+    pub preset_id: String,
+    pub preset_name: String,
+    /// `None` when the whole resources value was unreadable.
+    pub kind: Option<DownloadKind>,
+    pub file_name: String,
+    pub value: Value,
+    pub reason: String,
+}
+
+impl ResourceDownloads {
+    pub fn maps(&self) -> [(DownloadKind, Option<&ResourceMap>); 3] {
+        [
+            (DownloadKind::Checkpoint, self.checkpoint_downloads.as_ref()),
+            (DownloadKind::Lora, self.lora_downloads.as_ref()),
+            (DownloadKind::Embedding, self.embedding_downloads.as_ref()),
+        ]
+    }
+}
+
+/// Only bare file names are accepted so a download can never escape its folder.
+pub fn check_file_name(file_name: &str) -> Result<(), String> {
+    let is_bare = Path::new(file_name).file_name().map(|n| n == file_name).unwrap_or(false);
+    if !is_bare || file_name.contains(['/', '\\']) {
+        return Err(format!("Invalid file name: {}", file_name));
+    }
+    if !is_model_file(Path::new(file_name)) {
+        return Err(format!("Unsupported model file extension: {}", file_name));
+    }
+    Ok(())
+}
+
+fn check_url(url: &str) -> Result<(), String> {
+    let rest = url.strip_prefix("https://")
+        .or_else(|| url.strip_prefix("http://"))
+        .ok_or_else(|| format!("Download URL must start with http:// or https://: {}", url))?;
+    let host = rest.split(['/', '?', '#']).next().unwrap_or_default();
+    if host.is_empty() || url.chars().any(char::is_whitespace) {
+        return Err(format!("Invalid download URL: {}", url));
+    }
+    Ok(())
+}
+
+fn check_sha256(hash: &str) -> Result<(), String> {
+    if hash.len() != 64 || !hash.chars().all(|c| c.is_ascii_hexdigit()) {
+        return Err(format!("Invalid SHA-256 hash: {}", hash));
+    }
+    Ok(())
+}
+
+pub fn check_entry(file_name: &str, entry: &ResourceEntry) -> Result<(), String> {
+    check_file_name(file_name)?;
+    check_url(&entry.url)?;
+    if let Some(hash) = &entry.sha256 {
+        check_sha256(hash)?;
+    }
+    Ok(())
+}
+
+/// Checks every download entry of a preset and reports all problems at once.
+pub fn validate_resources(resources: Option<&ResourceDownloads>) -> Result<(), String> {
+    let Some(resources) = resources else {
+        return Ok(());
+    };
+
+    let problems: Vec<String> = resources.maps()
+        .into_iter()
+        .flat_map(|(kind, map)| map.into_iter().flatten().map(move |(name, entry)| (kind, name, entry)))
+        .filter_map(|(kind, name, entry)| {
+            check_entry(name, entry).err().map(|e| format!("{} {}: {}", kind.model_type(), name, e))
+        })
+        .collect();
+
+    if problems.is_empty() {
+        Ok(())
+    } else {
+        Err(problems.join("; "))
+    }
+}
+
+/// Converts one untyped `*_downloads` map, keeping the valid entries and
+/// returning the rest as `(file name, value, reason)`.
+fn convert_map(value: Option<&Value>) -> (Option<ResourceMap>, Vec<(String, Value, String)>) {
+    let Some(value) = value.filter(|v| !v.is_null()) else {
+        return (None, vec![]);
+    };
+    let Some(object) = value.as_object() else {
+        return (None, vec![(String::new(), value.clone(), "Not a map of file name to URL".to_string())]);
+    };
+
+    let mut map = ResourceMap::new();
+    let mut rejected = Vec::new();
+    for (file_name, raw) in object {
+        let entry = serde_json::from_value::<ResourceEntry>(raw.clone())
+            .map_err(|_| "Entry is neither a URL nor { url, sha256 }".to_string())
+            .and_then(|entry| check_entry(file_name, &entry).map(|_| entry));
+        match entry {
+            Ok(entry) => {
+                map.insert(file_name.clone(), entry);
+            }
+            Err(reason) => rejected.push((file_name.clone(), raw.clone(), reason)),
+        }
+    }
+    (Some(map), rejected)
+}
+
+/// One-off conversion of the untyped resource maps stored by earlier versions.
+/// Entries that cannot be converted are dropped from the preset and recorded
+/// in `resource_issues` so the user can fix them by hand. Runs in one
+/// transaction, so an interrupted conversion is redone from the start.
+pub fn migrate_resource_rows(conn: &Connection) -> SqliteResult<()> {
+    if get_setting(conn, MIGRATED_SETTING)?.is_some() {
+        return Ok(());
+    }
+    let tx = conn.unchecked_transaction()?;
+
+    let rows: Vec<(String, String, Option<String>)> = {
+        let mut stmt = tx.prepare("SELECT id, name, resources FROM presets WHERE resources IS NOT NULL")?;
+        let rows = stmt.query_map([], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)))?;
+        rows.collect::<SqliteResult<_>>()?
+    };
+
+    for (preset_id, preset_name, resources) in rows {
+        let mut issues = Vec::new();
+        let raw: Value = match resources.as_deref().map(serde_json::from_str::<Value>) {
+            Some(Ok(value)) => value,
+            Some(Err(e)) => {
+                issues.push(ResourceIssue {
+                    preset_id: preset_id.clone(),
+                    preset_name: preset_name.clone(),
+                    kind: None,
+                    file_name: String::new(),
+                    value: Value::String(resources.clone().unwrap_or_default()),
+                    reason: format!("Resources are not valid JSON: {}", e),
+                });
+                Value::Null
+            }
+            None => Value::Null,
+        };
+
+        let mut converted = ResourceDownloads::default();
+        for (kind, key) in [
+            (DownloadKind::Checkpoint, "checkpointDownloads"),
+            (DownloadKind::Lora, "loraDownloads"),
+            (DownloadKind::Embedding, "embeddingDownloads"),
+        ] {
+            let (map, rejected) = convert_map(raw.get(key));
+            match kind {
+                DownloadKind::Checkpoint => converted.checkpoint_downloads = map,
+                DownloadKind::Lora => converted.lora_downloads = map,
+                DownloadKind::Embedding => converted.embedding_downloads = map,
+            }
+            issues.extend(rejected.into_iter().map(|(file_name, value, reason)| ResourceIssue {
+                preset_id: preset_id.clone(),
+                preset_name: preset_name.clone(),
+                kind: Some(kind),
+                file_name,
+                value,
+                reason,
+            }));
+        }
+        if !raw.is_null() && !raw.is_object() {
+            issues.push(ResourceIssue {
+                preset_id: preset_id.clone(),
+                preset_name: preset_name.clone(),
+                kind: None,
+                file_name: String::new(),
+                value: raw.clone(),
+                reason: "Resources are not an object".to_string(),
+            });
+        }
+
+        let json = serde_json::to_string(&converted)
+            .map_err(|e| rusqlite::Error::ToSqlConversionFailure(Box::new(e)))?;
+        tx.execute("UPDATE presets SET resources = ?1 WHERE id = ?2", params![json, preset_id])?;
+        for issue in issues {
+            insert_issue(&tx, &issue)?;
+        }
+    }
+
+    set_setting(&tx, MIGRATED_SETTING, "1")?;
+    tx.commit()
+}
+
+fn insert_issue(conn: &Connection, issue: &ResourceIssue) -> SqliteResult<()> {
+    let kind = serde_json::to_string(&issue.kind)
+        .map_err(|e| rusqlite::Error::ToSqlConversionFailure(Box::new(e)))?;
+    conn.execute(
+        "INSERT INTO resource_issues (preset_id, preset_name, kind, file_name, value, reason)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+        params![
+            issue.preset_id,
+            issue.preset_name,
+            kind,
+            issue.file_name,
+            issue.value.to_string(),
+            issue.reason,
+        ],
+    )?;
+    Ok(())
+}
+
+pub fn load_issues(conn: &Connection) -> SqliteResult<Vec<ResourceIssue>> {
+    let mut stmt = conn.prepare(
+        "SELECT preset_id, preset_name, kind, file_name, value, reason FROM resource_issues ORDER BY rowid"
+    )?;
+    let issues = stmt.query_map([], |row| {
+        Ok(ResourceIssue {
+            preset_id: row.get(0)?,
+            preset_name: row.get(1)?,
+            kind: serde_json::from_str(&row.get::<_, String>(2)?).unwrap_or(None),
+            file_name: row.get(3)?,
+            value: serde_json::from_str(&row.get::<_, String>(4)?).unwrap_or(Value::Null),
+            reason: row.get(5)?,
+        })
+    })?;
+    issues.collect()
+}
@@ -0,0 +1,164 @@
+use crate::database::{update_preset_row, PresetConfig, PRESET_COLUMNS};
+use crate::diff;
+use rusqlite::{params, Connection, OptionalExtension, Result as SqliteResult};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+/// A stored state of a preset. The newest revision always matches the preset
+/// itself, as every write goes through `save_preset`; `changed_fields` is
+/// relative to the revision before it.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PresetRevision {
+    pub id: String,
+    pub preset_id: String,
+    pub revision: i32,
+    pub created_at: String,
+    pub changed_fields: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub restored_from: Option<i32>,
+}
+
+fn to_json<T: Serialize>(value: &T) -> SqliteResult<String> {
+    serde_json::to_string(value).map_err(|e| rusqlite::Error::ToSqlConversionFailure(Box::new(e)))
+}
+
+fn parse_snapshot(json: &str) -> SqliteResult<PresetConfig> {
+    serde_json::from_str(json).map_err(|e| {
+        rusqlite::Error::FromSqlConversionFailure(0, rusqlite::types::Type::Text, Box::new(e))
+    })
+}
+
+fn latest_snapshot(conn: &Connection, preset_id: &str) -> SqliteResult<Option<(i32, PresetConfig)>> {
+    let row: Option<(i32, String)> = conn.query_row(
+        "SELECT revision, snapshot FROM preset_revisions WHERE preset_id = ?1 ORDER BY revision DESC LIMIT 1",
+        params![preset_id],
+        |row| Ok((row.get(0)?, row.get(1)?)),
+    ).optional()?;
+    row.map(|(revision, json)| Ok((revision, parse_snapshot(&json)?))).transpose()
+}
+
+/// Stores `preset` as its newest revision unless nothing but bookkeeping
+/// fields changed since the last one.
+pub fn record_revision(conn: &Connection, preset: &PresetConfig, now: &str, restored_from: Option<i32>) -> SqliteResult<()> {
+    let (revision, changed_fields) = match latest_snapshot(conn, &preset.id)? {
+        Some((revision, previous)) => {
+            let changes = diff::diff_presets(&previous, preset)
+                .map_err(|e| rusqlite::Error::ToSqlConversionFailure(e.into()))?;
+            if changes.is_empty() && restored_from.is_none() {
+                return Ok(());
+            }
+            let mut paths: Vec<String> = Vec::new();
+            for change in &changes {
+                if !paths.iter().any(|p| p == change.path()) {
+                    paths.push(change.path().to_string());
+                }
+            }
+            (revision + 1, paths)
+        }
+        None => (1, vec![]),
+    };
+
+    conn.execute(
+        "INSERT INTO preset_revisions (id, preset_id, revision, snapshot, changed_fields, restored_from, created_at)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+        params![
+            Uuid::new_v4().to_string(),
+            preset.id,
+            revision,
+            to_json(preset)?,
+            to_json(&changed_fields)?,
+            restored_from,
+            now,
+        ],
+    )?;
+    Ok(())
+}
+
+/// Presets saved before revisions existed get their current row recorded as
+/// revision 1 before it is first overwritten.
+pub fn ensure_baseline(conn: &Connection, preset_id: &str) -> SqliteResult<()> {
+    let has_revisions: bool = conn.query_row(
+        "SELECT EXISTS(SELECT 1 FROM preset_revisions WHERE preset_id = ?1)",
+        params![preset_id],
+        |row| row.get(0),
+    )?;
+    if has_revisions {
+        return Ok(());
+    }
+
+    let current = conn.query_row(
+        &format!("SELECT {} FROM presets WHERE id = ?1", PRESET_COLUMNS),
+        params![preset_id],
+        PresetConfig::from_row,
+    ).optional()?;
+    match current {
+        Some(preset) => record_revision(conn, &preset, &preset.updated_at, None),
+        None => Ok(()),
+    }
+}
+
+/// Writes a changed preset and records it as its newest revision.
+pub fn save_preset(conn: &Connection, preset: &PresetConfig, now: &str) -> SqliteResult<()> {
+    ensure_baseline(conn, &preset.id)?;
+    update_preset_row(conn, preset)?;
+    record_revision(conn, preset, now, None)
+}
+
+/// Records a baseline for every preset without revisions. Run at startup, so
+/// reading the history never has to write.
+pub fn backfill_baselines(conn: &Connection) -> SqliteResult<()> {
+    let ids: Vec<String> = {
+        let mut stmt = conn.prepare(
+            "SELECT id FROM presets p WHERE NOT EXISTS (SELECT 1 FROM preset_revisions r WHERE r.preset_id = p.id)"
+        )?;
+        let rows = stmt.query_map([], |row| row.get(0))?;
+        rows.collect::<SqliteResult<_>>()?
+    };
+    for id in ids {
+        ensure_baseline(conn, &id)?;
+    }
+    Ok(())
+}
+
+pub fn load_revisions(conn: &Connection, preset_id: &str) -> SqliteResult<Vec<PresetRevision>> {
+    let mut stmt = conn.prepare(
+        "SELECT id, preset_id, revision, created_at, changed_fields, restored_from
+         FROM preset_revisions WHERE preset_id = ?1 ORDER BY revision DESC"
+    )?;
+    let revisions = stmt.query_map(params![preset_id], |row| {
+        Ok(PresetRevision {
+            id: row.get(0)?,
+            preset_id: row.get(1)?,
+            revision: row.get(2)?,
+            created_at: row.get(3)?,
+            changed_fields: serde_json::from_str(&row.get::<_, String>(4)?).unwrap_or_default(),
+            restored_from: row.get(5)?,
+        })
+    })?;
+    revisions.collect()
+}
+
+/// The revision number and stored preset of a revision.
+pub fn load_snapshot(conn: &Connection, revision_id: &str) -> SqliteResult<Option<(i32, PresetConfig)>> {
+    let row: Option<(i32, String)> = conn.query_row(
+        "SELECT revision, snapshot FROM preset_revisions WHERE id = ?1",
+        params![revision_id],
+        |row| Ok((row.get(0)?, row.get(1)?)),
+    ).optional()?;
+    row.map(|(revision, json)| Ok((revision, parse_snapshot(&json)?))).transpose()
+}
+
+/// Every stored state of a preset as (revision id, revision, preset), newest first.
+pub fn load_snapshots(conn: &Connection, preset_id: &str) -> SqliteResult<Vec<(String, i32, PresetConfig)>> {
+    let mut stmt = conn.prepare(
+        "SELECT id, revision, snapshot FROM preset_revisions WHERE preset_id = ?1 ORDER BY revision DESC"
+    )?;
+    let rows = stmt.query_map(params![preset_id], |row| {
+        Ok((row.get::<_, String>(0)?, row.get::<_, i32>(1)?, row.get::<_, String>(2)?))
+    })?;
+    rows.map(|row| {
+        let (id, revision, json) = row?;
+        Ok((id, revision, parse_snapshot(&json)?))
+    }).collect()
+}
@@ -0,0 +1,106 @@
+use crate::database::{ModelInfo, ModelType, PresetConfig};
+use crate::embeddings::preset_references_model;
+use crate::model_files::{file_stamp, resolve_model_file};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+const UNKNOWN_ARCHITECTURE: &str = "Unknown";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ModelStats {
+    pub model_id: String,
+    pub name: String,
+    pub file_name: String,
+    pub model_type: ModelType,
+    pub architecture: String,
+    /// `None` when the file could not be found on disk.
+    pub file_size: Option<u64>,
+    pub preset_count: i32,
+    pub total_use_count: i32,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SizeBucket {
+    pub key: String,
+    pub model_count: i32,
+    pub total_size: u64,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LibraryStats {
+    pub total_size: u64,
+    pub models: Vec<ModelStats>,
+    pub by_type: Vec<SizeBucket>,
+    pub by_architecture: Vec<SizeBucket>,
+    /// Models no preset refers to, largest first.
+    pub orphans: Vec<ModelStats>,
+    pub missing_files: Vec<ModelStats>,
+}
+
+fn add_to_bucket(buckets: &mut BTreeMap<String, SizeBucket>, key: &str, size: u64) {
+    let bucket = buckets.entry(key.to_string()).or_insert_with(|| SizeBucket {
+        key: key.to_string(),
+        ..SizeBucket::default()
+    });
+    bucket.model_count += 1;
+    bucket.total_size += size;
+}
+
+fn sorted_buckets(buckets: BTreeMap<String, SizeBucket>) -> Vec<SizeBucket> {
+    let mut buckets: Vec<SizeBucket> = buckets.into_values().collect();
+    buckets.sort_by_key(|b| std::cmp::Reverse(b.total_size));
+    buckets
+}
+
+pub fn model_stats(model: &ModelInfo, presets: &[PresetConfig], models: &[ModelInfo]) -> ModelStats {
+    let users: Vec<&PresetConfig> = presets.iter()
+        .filter(|p| preset_references_model(p, &model.id, models))
+        .collect();
+
+    ModelStats {
+        model_id: model.id.clone(),
+        name: model.name.clone(),
+        file_name: model.file_name.clone(),
+        model_type: model.model_type,
+        architecture: model.recommended_base_model.clone()
+            .filter(|a| !a.trim().is_empty())
+            .unwrap_or_else(|| UNKNOWN_ARCHITECTURE.to_string()),
+        file_size: resolve_model_file(model)
+            .and_then(|path| file_stamp(&path).ok())
+            .map(|stamp| stamp.size),
+        preset_count: users.len() as i32,
+        total_use_count: users.iter().map(|p| p.use_count).sum(),
+    }
+}
+
+pub fn library_stats(models: &[ModelInfo], presets: &[PresetConfig]) -> LibraryStats {
+    let mut stats = LibraryStats::default();
+    let mut by_type = BTreeMap::new();
+    let mut by_architecture = BTreeMap::new();
+
+    for model in models {
+        let entry = model_stats(model, presets, models);
+        let size = entry.file_size.unwrap_or(0);
+
+        stats.total_size += size;
+        add_to_bucket(&mut by_type, entry.model_type.as_str(), size);
+        add_to_bucket(&mut by_architecture, &entry.architecture, size);
+
+        if entry.file_size.is_none() {
+            stats.missing_files.push(entry.clone());
+        }
+        if entry.preset_count == 0 {
+            stats.orphans.push(entry.clone());
+        }
+        stats.models.push(entry);
+    }
+
+    stats.models.sort_by_key(|m| std::cmp::Reverse(m.file_size));
+    stats.orphans.sort_by_key(|m| std::cmp::Reverse(m.file_size));
+    stats.by_type = sorted_buckets(by_type);
+    stats.by_architecture = sorted_buckets(by_architecture);
+    stats
+}
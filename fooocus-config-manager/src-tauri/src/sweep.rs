@@ -0,0 +1,211 @@
+use crate::bulk::{self, PresetEdit};
+use crate::database::PresetConfig;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use uuid::Uuid;
+
+/// Sections whose fields can be swept.
+const SWEEP_SECTIONS: &[&str] = &["sampling", "model", "image"];
+/// Upper bound on the size of the Cartesian product.
+const MAX_VARIANTS: usize = 256;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "camelCase", rename_all_fields = "camelCase")]
+pub enum SweepValues {
+    List { values: Vec<Value> },
+    /// `start`, `start + step`, ... up to and including `end`.
+    Range { start: f64, end: f64, step: f64 },
+}
+
+/// One dimension of a sweep, e.g. `sampling.cfgScale` over 4..=7 or
+/// `model.loras.0.weight` over 0.2..=1.0.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SweepAxis {
+    pub path: String,
+    pub values: SweepValues,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SweepSetting {
+    pub path: String,
+    pub value: Value,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SweepVariant {
+    pub preset: PresetConfig,
+    /// The value each axis takes in this variant, in axis order.
+    pub settings: Vec<SweepSetting>,
+}
+
+/// Where generated variants are saved.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "camelCase", rename_all_fields = "camelCase")]
+pub enum SweepTarget {
+    Library,
+    Collection { collection_id: String },
+    NewCollection { name: String, parent_id: Option<String> },
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PresetSweep {
+    pub variants: Vec<SweepVariant>,
+    /// The collection the variants were saved into, if any.
+    pub collection_id: Option<String>,
+}
+
+impl SweepValues {
+    fn expand(&self, path: &str) -> Result<Vec<Value>, String> {
+        let values = match self {
+            SweepValues::List { values } => values.clone(),
+            SweepValues::Range { start, end, step } => {
+                if *step <= 0.0 || end < start {
+                    return Err(format!("Invalid range for {}: {} to {} in steps of {}", path, start, end, step));
+                }
+                let count = ((end - start) / step + 1e-9).floor() as usize + 1;
+                if count > MAX_VARIANTS {
+                    return Err(format!("Range for {} has too many values", path));
+                }
+                (0..count)
+                    .map(|i| {
+                        // Rounded so 0.2 steps give 0.6 rather than 0.6000000000000001.
+                        let n = ((start + i as f64 * step) * 1e6).round() / 1e6;
+                        if n.fract() == 0.0 {
+                            Value::from(n as i64)
+                        } else {
+                            Value::from(n)
+                        }
+                    })
+                    .collect()
+            }
+        };
+        if values.is_empty() {
+            return Err(format!("No values to sweep for {}", path));
+        }
+        Ok(values)
+    }
+}
+
+/// `cfgScale=4` style name part; LoRA fields are named after the LoRA.
+fn label(base: &PresetConfig, setting: &SweepSetting) -> String {
+    let mut key = setting.path.split_once('.').map_or(setting.path.clone(), |(_, key)| key.to_string());
+    if let Some((index, field)) = key.strip_prefix("loras.").and_then(|rest| rest.split_once('.')) {
+        if let Some(lora) = index.parse::<usize>().ok().and_then(|i| base.model.loras.get(i)) {
+            key = format!("{}.{}", lora.name, field);
+        }
+    }
+    let value = match &setting.value {
+        Value::String(value) => value.clone(),
+        value => value.to_string(),
+    };
+    format!("{}={}", key, value)
+}
+
+/// Builds one variant of `base` per combination of axis values. The first
+/// axis varies slowest. Variants are new presets forked from `base`; the model
+/// ids of swept model names are cleared, so the caller should relink them.
+pub fn generate(base: &PresetConfig, axes: &[SweepAxis], now: &str) -> Result<Vec<SweepVariant>, String> {
+    if axes.is_empty() {
+        return Err("A sweep needs at least one axis".to_string());
+    }
+
+    let mut combinations: Vec<Vec<SweepSetting>> = vec![Vec::new()];
+    for (index, axis) in axes.iter().enumerate() {
+        let section = axis.path.split('.').next().unwrap_or_default();
+        if !SWEEP_SECTIONS.contains(&section) || !axis.path.contains('.') {
+            return Err(format!("Cannot sweep {}: only sampling, model and image fields can be swept", axis.path));
+        }
+        if axes[..index].iter().any(|a| a.path == axis.path) {
+            return Err(format!("{} is swept twice", axis.path));
+        }
+
+        let values = axis.values.expand(&axis.path)?;
+        if combinations.len() * values.len() > MAX_VARIANTS {
+            return Err(format!("A sweep can produce at most {} variants", MAX_VARIANTS));
+        }
+        combinations = combinations.into_iter()
+            .flat_map(|settings| values.iter().map(move |value| {
+                let mut settings = settings.clone();
+                settings.push(SweepSetting { path: axis.path.clone(), value: value.clone() });
+                settings
+            }))
+            .collect();
+    }
+
+    combinations.into_iter()
+        .map(|settings| {
+            let edits: Vec<PresetEdit> = settings.iter()
+                .map(|s| PresetEdit::SetField { path: s.path.clone(), value: s.value.clone() })
+                .collect();
+            let varied = bulk::apply_edits(base, &edits)?;
+            let labels: Vec<String> = settings.iter().map(|s| label(base, s)).collect();
+            let preset = PresetConfig {
+                id: Uuid::new_v4().to_string(),
+                name: format!("{} [{}]", base.name, labels.join(", ")),
+                is_favorite: false,
+                use_count: 0,
+                rating: None,
+                created_at: now.to_string(),
+                updated_at: now.to_string(),
+                source_preset_id: Some(base.id.clone()),
+                ..varied
+            };
+            Ok(SweepVariant { preset, settings })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::{test_preset, ModelInfo, ModelType};
+    use crate::linking::{self, RelinkReport};
+    use serde_json::json;
+
+    fn checkpoint(id: &str, file_name: &str) -> ModelInfo {
+        ModelInfo {
+            id: id.to_string(),
+            name: id.to_string(),
+            file_name: file_name.to_string(),
+            model_type: ModelType::Checkpoint,
+            description: String::new(),
+            scope: vec![],
+            path: String::new(),
+            tags: vec![],
+            created_at: String::new(),
+            updated_at: String::new(),
+            trigger_words: vec![],
+            recommended_weight_min: None,
+            recommended_weight_max: None,
+            recommended_base_model: None,
+            source_url: None,
+            preview_image: None,
+        }
+    }
+
+    #[test]
+    fn model_name_axis_relinks_each_variant() {
+        let models = vec![checkpoint("a", "a.safetensors"), checkpoint("b", "b.safetensors")];
+        let mut base = test_preset("base");
+        base.model.base_model = "a.safetensors".to_string();
+        base.model.base_model_id = Some("a".to_string());
+
+        let axes = vec![SweepAxis {
+            path: "model.baseModel".to_string(),
+            values: SweepValues::List { values: vec![json!("a.safetensors"), json!("b.safetensors")] },
+        }];
+        let mut variants = generate(&base, &axes, "2024-01-01T00:00:00+00:00").unwrap();
+        for variant in &mut variants {
+            linking::link_preset(&mut variant.preset, &models, &mut RelinkReport::default());
+        }
+
+        let linked: Vec<(&str, Option<&str>)> = variants.iter()
+            .map(|v| (v.preset.model.base_model.as_str(), v.preset.model.base_model_id.as_deref()))
+            .collect();
+        assert_eq!(linked, vec![("a.safetensors", Some("a")), ("b.safetensors", Some("b"))]);
+    }
+}
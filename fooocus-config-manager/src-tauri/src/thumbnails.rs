@@ -0,0 +1,134 @@
+use crate::metadata::sidecar_path;
+use base64::Engine;
+use image::imageops::FilterType;
+use std::path::{Path, PathBuf};
+
+pub const THUMBNAIL_SIZES: [u32; 3] = [128, 256, 512];
+
+pub const PREVIEW_SIDECAR_SUFFIXES: &[&str] = &[".preview.png", ".preview.jpg", ".preview.jpeg", ".preview.webp", ".png"];
+
+const IMAGE_EXTENSIONS: &[&str] = &["png", "jpg", "jpeg", "webp"];
+
+pub fn is_image_file(path: &Path) -> bool {
+    path.extension()
+        .and_then(|e| e.to_str())
+        .map(|e| IMAGE_EXTENSIONS.iter().any(|i| e.eq_ignore_ascii_case(i)))
+        .unwrap_or(false)
+}
+
+/// Finds a preview image saved next to a model file, e.g. `foo.preview.png`.
+pub fn find_preview_sidecar(model_file: &Path) -> Option<PathBuf> {
+    PREVIEW_SIDECAR_SUFFIXES.iter()
+        .map(|suffix| sidecar_path(model_file, suffix))
+        .find(|path| path.is_file())
+}
+
+/// Snaps a requested size to the smallest generated size that is at least as large.
+pub fn snap_size(size: u32) -> u32 {
+    THUMBNAIL_SIZES.iter()
+        .copied()
+        .find(|s| *s >= size)
+        .unwrap_or(THUMBNAIL_SIZES[THUMBNAIL_SIZES.len() - 1])
+}
+
+pub fn thumbnail_path(dir: &Path, key: &str, size: u32) -> PathBuf {
+    dir.join(format!("{}_{}.jpg", key, size))
+}
+
+/// Copies `source` into `dir` as `<key>.<ext>` and returns the stored path.
+pub fn store_image(source: &Path, dir: &Path, key: &str) -> Result<PathBuf, String> {
+    if !is_image_file(source) {
+        return Err(format!("Unsupported image file: {}", source.display()));
+    }
+    std::fs::create_dir_all(dir).map_err(|e| e.to_string())?;
+    let ext = source.extension().and_then(|e| e.to_str()).unwrap_or("png").to_lowercase();
+    let stored = dir.join(format!("{}.{}", key, ext));
+    std::fs::copy(source, &stored).map_err(|e| e.to_string())?;
+    Ok(stored)
+}
+
+/// Stores `source` as `<key>.<ext>` in `dir` and renders its thumbnails into
+/// `thumbnail_dir`. The image is staged under a temporary name, so an earlier
+/// image stored under `key` is only replaced once the new one decoded.
+pub fn replace_image(source: &Path, dir: &Path, thumbnail_dir: &Path, key: &str) -> Result<PathBuf, String> {
+    let staged = store_image(source, dir, &format!("{}.staged-{}", key, uuid::Uuid::new_v4()))?;
+    if let Err(e) = generate_thumbnails(&staged, thumbnail_dir, key) {
+        std::fs::remove_file(&staged).ok();
+        return Err(e);
+    }
+    let ext = staged.extension().and_then(|e| e.to_str()).unwrap_or("png");
+    let stored = dir.join(format!("{}.{}", key, ext));
+    if let Err(e) = std::fs::rename(&staged, &stored) {
+        std::fs::remove_file(&staged).ok();
+        return Err(e.to_string());
+    }
+    Ok(stored)
+}
+
+/// Renders every size in `THUMBNAIL_SIZES` for `source` into `dir`.
+pub fn generate_thumbnails(source: &Path, dir: &Path, key: &str) -> Result<(), String> {
+    std::fs::create_dir_all(dir).map_err(|e| e.to_string())?;
+    let image = image::open(source).map_err(|e| format!("Failed to decode {}: {}", source.display(), e))?;
+    for size in THUMBNAIL_SIZES {
+        let thumbnail = image.resize(size, size, FilterType::Triangle).into_rgb8();
+        thumbnail.save(thumbnail_path(dir, key, size)).map_err(|e| e.to_string())?;
+    }
+    Ok(())
+}
+
+pub fn remove_thumbnails(dir: &Path, key: &str) {
+    for size in THUMBNAIL_SIZES {
+        std::fs::remove_file(thumbnail_path(dir, key, size)).ok();
+    }
+}
+
+/// Returns the cached thumbnail as a data URL, regenerating it when the source is newer.
+pub fn thumbnail_data_url(source: &Path, dir: &Path, key: &str, size: u32) -> Result<String, String> {
+    let path = thumbnail_path(dir, key, snap_size(size));
+    let stale = match (std::fs::metadata(&path), std::fs::metadata(source)) {
+        (Ok(thumb), Ok(src)) => match (thumb.modified(), src.modified()) {
+            (Ok(thumb), Ok(src)) => thumb < src,
+            _ => false,
+        },
+        (Err(_), _) => true,
+        (Ok(_), Err(_)) => false,
+    };
+    if stale {
+        generate_thumbnails(source, dir, key)?;
+    }
+
+    let bytes = std::fs::read(&path).map_err(|e| e.to_string())?;
+    Ok(format!(
+        "data:image/jpeg;base64,{}",
+        base64::engine::general_purpose::STANDARD.encode(bytes)
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_dir() -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("thumbnails-test-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn keeps_previous_image_when_replacement_does_not_decode() {
+        let dir = temp_dir();
+        let (previews, thumbnails) = (dir.join("previews"), dir.join("thumbnails"));
+        let good = dir.join("good.png");
+        image::RgbImage::from_pixel(4, 4, image::Rgb([200, 10, 10])).save(&good).unwrap();
+        let bad = dir.join("bad.png");
+        std::fs::write(&bad, b"not a png").unwrap();
+
+        let stored = replace_image(&good, &previews, &thumbnails, "model").unwrap();
+        let result = replace_image(&bad, &previews, &thumbnails, "model");
+
+        assert!(result.is_err());
+        assert_eq!(std::fs::read(&stored).unwrap(), std::fs::read(&good).unwrap());
+        assert_eq!(std::fs::read_dir(&previews).unwrap().count(), 1);
+        std::fs::remove_dir_all(&dir).ok();
+    }
+}
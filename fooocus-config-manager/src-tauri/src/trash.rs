@@ -0,0 +1,133 @@
+use crate::database::get_setting;
+use crate::gallery::{self, PresetImage};
+use rusqlite::{params, Connection, OptionalExtension, Result as SqliteResult};
+use serde::{Deserialize, Serialize};
+
+pub const RETENTION_SETTING: &str = "trash_retention_days";
+pub const DEFAULT_RETENTION_DAYS: u32 = 30;
+
+/// Why the last automatic purge failed; empty once a purge succeeds.
+pub const PURGE_ERROR_SETTING: &str = "trash_purge_error";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum TrashKind {
+    Preset,
+    Model,
+    Tag,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TrashItem {
+    pub kind: TrashKind,
+    pub id: String,
+    pub name: String,
+    pub deleted_at: String,
+}
+
+impl TrashKind {
+    const ALL: [TrashKind; 3] = [TrashKind::Preset, TrashKind::Model, TrashKind::Tag];
+
+    fn table(self) -> &'static str {
+        match self {
+            TrashKind::Preset => "presets",
+            TrashKind::Model => "models",
+            TrashKind::Tag => "tags",
+        }
+    }
+}
+
+/// Marks a live record as deleted. Returns false if there was none.
+pub fn move_to_trash(conn: &Connection, kind: TrashKind, id: &str, now: &str) -> SqliteResult<bool> {
+    let changed = conn.execute(
+        &format!("UPDATE {} SET deleted_at = ?1 WHERE id = ?2 AND deleted_at IS NULL", kind.table()),
+        params![now, id],
+    )?;
+    Ok(changed > 0)
+}
+
+/// Brings a trashed record back. Returns false if it is not in the trash.
+pub fn restore(conn: &Connection, kind: TrashKind, id: &str) -> SqliteResult<bool> {
+    let changed = conn.execute(
+        &format!("UPDATE {} SET deleted_at = NULL WHERE id = ?1 AND deleted_at IS NOT NULL", kind.table()),
+        params![id],
+    )?;
+    Ok(changed > 0)
+}
+
+fn load_trashed(conn: &Connection, kind: TrashKind, cutoff: Option<&str>) -> SqliteResult<Vec<TrashItem>> {
+    let mut stmt = conn.prepare(&format!(
+        "SELECT id, name, deleted_at FROM {} WHERE deleted_at IS NOT NULL AND (?1 IS NULL OR deleted_at <= ?1)
+         ORDER BY deleted_at DESC",
+        kind.table()
+    ))?;
+    let items = stmt.query_map(params![cutoff], |row| {
+        Ok(TrashItem {
+            kind,
+            id: row.get(0)?,
+            name: row.get(1)?,
+            deleted_at: row.get(2)?,
+        })
+    })?;
+    items.collect()
+}
+
+pub fn load_trash(conn: &Connection) -> SqliteResult<Vec<TrashItem>> {
+    let mut items = Vec::new();
+    for kind in TrashKind::ALL {
+        items.extend(load_trashed(conn, kind, None)?);
+    }
+    items.sort_by(|a, b| b.deleted_at.cmp(&a.deleted_at));
+    Ok(items)
+}
+
+pub fn retention_days(conn: &Connection) -> SqliteResult<u32> {
+    Ok(get_setting(conn, RETENTION_SETTING)?
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(DEFAULT_RETENTION_DAYS))
+}
+
+/// Files left behind by purged records, removed once the purge is committed.
+#[derive(Debug, Default)]
+pub struct PurgedFiles {
+    pub images: Vec<PresetImage>,
+    /// Preview images of purged models; their thumbnails are keyed by model id.
+    pub model_previews: Vec<(String, Option<String>)>,
+}
+
+/// Permanently deletes trashed records deleted at or before `cutoff`, or all
+/// of them without one. Returns the purged items and the files the caller
+/// should remove once the transaction is committed.
+pub fn purge(conn: &Connection, cutoff: Option<&str>) -> SqliteResult<(Vec<TrashItem>, PurgedFiles)> {
+    let mut purged = Vec::new();
+    let mut files = PurgedFiles::default();
+
+    for kind in TrashKind::ALL {
+        for item in load_trashed(conn, kind, cutoff)? {
+            match kind {
+                TrashKind::Preset => {
+                    files.images.extend(gallery::load_preset_images(conn, &item.id)?);
+                    conn.execute("DELETE FROM preset_images WHERE preset_id = ?1", params![item.id])?;
+                    conn.execute("DELETE FROM preset_revisions WHERE preset_id = ?1", params![item.id])?;
+                    conn.execute("DELETE FROM collection_presets WHERE preset_id = ?1", params![item.id])?;
+                    conn.execute("DELETE FROM preset_outcomes WHERE preset_id = ?1", params![item.id])?;
+                }
+                TrashKind::Model => {
+                    let preview: Option<String> = conn.query_row(
+                        "SELECT preview_image FROM models WHERE id = ?1",
+                        params![item.id],
+                        |row| row.get(0),
+                    ).optional()?.flatten();
+                    files.model_previews.push((item.id.clone(), preview));
+                    conn.execute("DELETE FROM model_family_versions WHERE model_id = ?1", params![item.id])?;
+                }
+                TrashKind::Tag => {}
+            }
+            conn.execute(&format!("DELETE FROM {} WHERE id = ?1", kind.table()), params![item.id])?;
+            purged.push(item);
+        }
+    }
+
+    Ok((purged, files))
+}
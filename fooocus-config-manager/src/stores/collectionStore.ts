@@ -0,0 +1,168 @@
+import { defineStore } from 'pinia';
+import { invoke } from '@tauri-apps/api/core';
+import type { Collection, CollectionExport } from '../types';
+
+interface CollectionState {
+  collections: Collection[];
+  isLoading: boolean;
+  error: string | null;
+}
+
+export const useCollectionStore = defineStore('collection', {
+  state: (): CollectionState => ({
+    collections: [],
+    isLoading: false,
+    error: null,
+  }),
+
+  getters: {
+    getCollectionById: (state) => (id: string) => state.collections.find(c => c.id === id),
+
+    // Sub-collections of `parentId` (top level for null), in their manual order.
+    childrenOf: (state) => (parentId: string | null) =>
+      state.collections
+        .filter(c => (c.parentId ?? null) === parentId)
+        .sort((a, b) => a.position - b.position),
+  },
+
+  actions: {
+    async fetchCollections() {
+      this.isLoading = true;
+      this.error = null;
+      try {
+        this.collections = await invoke<Collection[]>('get_collections');
+      } catch (e) {
+        this.error = String(e);
+        console.error('Failed to fetch collections:', e);
+      } finally {
+        this.isLoading = false;
+      }
+    },
+
+    replaceCollection(collection: Collection) {
+      const index = this.collections.findIndex(c => c.id === collection.id);
+      if (index !== -1) {
+        this.collections[index] = collection;
+      } else {
+        this.collections.push(collection);
+      }
+    },
+
+    async createCollection(name: string, parentId: string | null = null, description = '') {
+      this.error = null;
+      try {
+        const created = await invoke<Collection>('create_collection', {
+          collection: { id: '', name, description, parentId, createdAt: '', updatedAt: '' },
+        });
+        this.replaceCollection(created);
+        return created;
+      } catch (e) {
+        this.error = String(e);
+        console.error('Failed to create collection:', e);
+        return null;
+      }
+    },
+
+    async updateCollection(collection: Collection) {
+      this.error = null;
+      try {
+        const updated = await invoke<Collection>('update_collection', { collection });
+        this.replaceCollection(updated);
+        return updated;
+      } catch (e) {
+        this.error = String(e);
+        console.error('Failed to update collection:', e);
+        return null;
+      }
+    },
+
+    async deleteCollection(id: string) {
+      this.error = null;
+      try {
+        await invoke('delete_collection', { id });
+        await this.fetchCollections();
+        return true;
+      } catch (e) {
+        this.error = String(e);
+        console.error('Failed to delete collection:', e);
+        return false;
+      }
+    },
+
+    async moveCollection(id: string, parentId: string | null, position?: number) {
+      this.error = null;
+      try {
+        this.collections = await invoke<Collection[]>('move_collection', { id, parentId, position });
+        return true;
+      } catch (e) {
+        this.error = String(e);
+        console.error('Failed to move collection:', e);
+        return false;
+      }
+    },
+
+    async addPresets(collectionId: string, presetIds: string[], position?: number) {
+      this.error = null;
+      try {
+        this.replaceCollection(await invoke<Collection>('add_presets_to_collection', { collectionId, presetIds, position }));
+        return true;
+      } catch (e) {
+        this.error = String(e);
+        console.error('Failed to add presets to collection:', e);
+        return false;
+      }
+    },
+
+    async removePresets(collectionId: string, presetIds: string[]) {
+      this.error = null;
+      try {
+        this.replaceCollection(await invoke<Collection>('remove_presets_from_collection', { collectionId, presetIds }));
+        return true;
+      } catch (e) {
+        this.error = String(e);
+        console.error('Failed to remove presets from collection:', e);
+        return false;
+      }
+    },
+
+    async movePresets(presetIds: string[], fromCollectionId: string, toCollectionId: string, position?: number) {
+      this.error = null;
+      try {
+        this.collections = await invoke<Collection[]>('move_presets_to_collection', {
+          presetIds,
+          fromCollectionId,
+          toCollectionId,
+          position,
+        });
+        return true;
+      } catch (e) {
+        this.error = String(e);
+        console.error('Failed to move presets between collections:', e);
+        return false;
+      }
+    },
+
+    async reorderPresets(collectionId: string, presetIds: string[]) {
+      this.error = null;
+      try {
+        this.replaceCollection(await invoke<Collection>('reorder_collection_presets', { collectionId, presetIds }));
+        return true;
+      } catch (e) {
+        this.error = String(e);
+        console.error('Failed to reorder collection presets:', e);
+        return false;
+      }
+    },
+
+    async exportCollection(id: string) {
+      this.error = null;
+      try {
+        return await invoke<CollectionExport>('export_collection', { id });
+      } catch (e) {
+        this.error = String(e);
+        console.error('Failed to export collection:', e);
+        return null;
+      }
+    },
+  },
+});
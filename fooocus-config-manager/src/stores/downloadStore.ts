@@ -0,0 +1,75 @@
+import { defineStore } from 'pinia';
+import { invoke } from '@tauri-apps/api/core';
+import { listen } from '@tauri-apps/api/event';
+import type { FooocusPaths, DownloadProgress, DownloadReport, ResourceDownloads } from '../types';
+import { useModelStore } from './modelStore';
+import { usePresetStore } from './presetStore';
+
+interface DownloadState {
+  paths: FooocusPaths;
+  progress: Record<string, DownloadProgress>;
+  isDownloading: boolean;
+  error: string | null;
+}
+
+export const useDownloadStore = defineStore('download', {
+  state: (): DownloadState => ({
+    paths: {
+      checkpointsDir: '',
+      lorasDir: '',
+      embeddingsDir: '',
+    },
+    progress: {},
+    isDownloading: false,
+    error: null,
+  }),
+
+  actions: {
+    async fetchPaths() {
+      this.error = null;
+      try {
+        this.paths = await invoke<FooocusPaths>('get_fooocus_paths');
+      } catch (e) {
+        this.error = String(e);
+        console.error('Failed to fetch Fooocus paths:', e);
+      }
+    },
+
+    async savePaths(paths: FooocusPaths) {
+      this.error = null;
+      try {
+        await invoke('set_fooocus_paths', { paths });
+        this.paths = paths;
+        return true;
+      } catch (e) {
+        this.error = String(e);
+        console.error('Failed to save Fooocus paths:', e);
+        return false;
+      }
+    },
+
+    async downloadResources(resources: ResourceDownloads, concurrency?: number) {
+      this.isDownloading = true;
+      this.error = null;
+      this.progress = {};
+      const unlisten = await listen<DownloadProgress>('download-progress', (event) => {
+        this.progress[`${event.payload.kind}/${event.payload.fileName}`] = event.payload;
+      });
+      try {
+        const report = await invoke<DownloadReport>('download_resources', { resources, concurrency });
+        if (report.registered.length > 0) {
+          await useModelStore().fetchModels();
+          await usePresetStore().fetchPresets();
+        }
+        return report;
+      } catch (e) {
+        this.error = String(e);
+        console.error('Failed to download resources:', e);
+        return null;
+      } finally {
+        unlisten();
+        this.isDownloading = false;
+      }
+    },
+  },
+});
@@ -1,11 +1,13 @@
 import { defineStore } from 'pinia';
 import { invoke } from '@tauri-apps/api/core';
-import type { ModelInfo, ModelType, ModelFilterOptions, ModelUsageInfo } from '../types';
+import type { ModelInfo, ModelType, ModelFilterOptions, ModelUsageInfo, ModelUpdateResult, DeleteStrategy, ModelDeletionResult, DuplicateReport, ModelMergeResult, SidecarImportPreview, ThumbnailSize, LibraryStats, ModelFamily, PresetUpgrade, PresetSummary } from '../types';
 import { createEmptyModelInfo } from '../types';
+import { usePresetStore } from './presetStore';
 
 interface ModelState {
   models: ModelInfo[];
   currentModel: ModelInfo | null;
+  families: ModelFamily[];
   filter: ModelFilterOptions;
   isLoading: boolean;
   error: string | null;
@@ -15,6 +17,7 @@ export const useModelStore = defineStore('model', {
   state: (): ModelState => ({
     models: [],
     currentModel: null,
+    families: [],
     filter: {
       search: '',
       type: '',
@@ -73,6 +76,7 @@ export const useModelStore = defineStore('model', {
     loras: (state) => state.models.filter(m => m.type === 'LoRA'),
     refiners: (state) => state.models.filter(m => m.type === 'Refiner'),
     embeddings: (state) => state.models.filter(m => m.type === 'Embedding'),
+    vaes: (state) => state.models.filter(m => m.type === 'VAE'),
 
     modelsByType: (state) => (type: ModelType) => state.models.filter(m => m.type === type),
 
@@ -166,15 +170,18 @@ export const useModelStore = defineStore('model', {
       this.isLoading = true;
       this.error = null;
       try {
-        const updated = await invoke<ModelInfo>('update_model', { model });
+        const result = await invoke<ModelUpdateResult>('update_model', { model });
         const index = this.models.findIndex(m => m.id === model.id);
         if (index !== -1) {
-          this.models[index] = updated;
+          this.models[index] = result.model;
         }
         if (this.currentModel?.id === model.id) {
-          this.currentModel = updated;
+          this.currentModel = result.model;
         }
-        return updated;
+        if (result.touchedPresets.length > 0) {
+          await usePresetStore().fetchPresets();
+        }
+        return result;
       } catch (e) {
         this.error = String(e);
         console.error('Failed to update model:', e);
@@ -184,23 +191,42 @@ export const useModelStore = defineStore('model', {
       }
     },
 
-    async deleteModel(id: string) {
+    async deleteModel(id: string, strategy?: DeleteStrategy) {
       this.isLoading = true;
       this.error = null;
       try {
-        await invoke('delete_model', { id });
+        const result = await invoke<ModelDeletionResult>('delete_model', { id, strategy });
         this.models = this.models.filter(m => m.id !== id);
         if (this.currentModel?.id === id) {
           this.currentModel = null;
         }
+        if (result.touchedPresets.length > 0) {
+          await usePresetStore().fetchPresets();
+        }
+        return result;
       } catch (e) {
         this.error = String(e);
         console.error('Failed to delete model:', e);
+        return null;
       } finally {
         this.isLoading = false;
       }
     },
 
+    async undoModelDeletion(undoId: string) {
+      this.error = null;
+      try {
+        const model = await invoke<ModelInfo>('undo_model_deletion', { undoId });
+        this.models.unshift(model);
+        await usePresetStore().fetchPresets();
+        return model;
+      } catch (e) {
+        this.error = String(e);
+        console.error('Failed to undo model deletion:', e);
+        return null;
+      }
+    },
+
     async searchModels(query: string) {
       this.isLoading = true;
       this.error = null;
@@ -226,6 +252,217 @@ export const useModelStore = defineStore('model', {
       }
     },
 
+    async importModelHints(id: string) {
+      this.error = null;
+      try {
+        const model = await invoke<ModelInfo>('import_model_hints', { id });
+        const index = this.models.findIndex(m => m.id === id);
+        if (index !== -1) {
+          this.models[index] = model;
+        }
+        return model;
+      } catch (e) {
+        this.error = String(e);
+        console.error('Failed to import model hints:', e);
+        return null;
+      }
+    },
+
+    async previewSidecarImport(modelIds?: string[]) {
+      this.error = null;
+      try {
+        return await invoke<SidecarImportPreview[]>('preview_sidecar_import', { modelIds });
+      } catch (e) {
+        this.error = String(e);
+        console.error('Failed to preview sidecar import:', e);
+        return [];
+      }
+    },
+
+    async applySidecarImport(modelId: string, fields?: string[]) {
+      this.error = null;
+      try {
+        const model = await invoke<ModelInfo>('apply_sidecar_import', { modelId, fields });
+        const index = this.models.findIndex(m => m.id === modelId);
+        if (index !== -1) {
+          this.models[index] = model;
+        }
+        return model;
+      } catch (e) {
+        this.error = String(e);
+        console.error('Failed to apply sidecar import:', e);
+        return null;
+      }
+    },
+
+    async findDuplicateModels(directories?: string[]) {
+      this.isLoading = true;
+      this.error = null;
+      try {
+        return await invoke<DuplicateReport>('find_duplicate_models', { directories });
+      } catch (e) {
+        this.error = String(e);
+        console.error('Failed to find duplicate models:', e);
+        return null;
+      } finally {
+        this.isLoading = false;
+      }
+    },
+
+    async mergeDuplicateModels(canonicalId: string, duplicateIds: string[]) {
+      this.error = null;
+      try {
+        const result = await invoke<ModelMergeResult>('merge_duplicate_models', { canonicalId, duplicateIds });
+        this.models = this.models.filter(m => !result.removedModelIds.includes(m.id));
+        if (result.touchedPresets.length > 0) {
+          await usePresetStore().fetchPresets();
+        }
+        return result;
+      } catch (e) {
+        this.error = String(e);
+        console.error('Failed to merge duplicate models:', e);
+        return null;
+      }
+    },
+
+    async setModelPreview(modelId: string, sourcePath: string) {
+      this.error = null;
+      try {
+        const model = await invoke<ModelInfo>('set_model_preview', { modelId, sourcePath });
+        const index = this.models.findIndex(m => m.id === modelId);
+        if (index !== -1) {
+          this.models[index] = model;
+        }
+        return model;
+      } catch (e) {
+        this.error = String(e);
+        console.error('Failed to set model preview:', e);
+        return null;
+      }
+    },
+
+    async clearModelPreview(modelId: string) {
+      this.error = null;
+      try {
+        const model = await invoke<ModelInfo>('clear_model_preview', { modelId });
+        const index = this.models.findIndex(m => m.id === modelId);
+        if (index !== -1) {
+          this.models[index] = model;
+        }
+        return model;
+      } catch (e) {
+        this.error = String(e);
+        console.error('Failed to clear model preview:', e);
+        return null;
+      }
+    },
+
+    async importModelPreviews() {
+      this.error = null;
+      try {
+        const imported = await invoke<ModelInfo[]>('import_model_previews');
+        for (const model of imported) {
+          const index = this.models.findIndex(m => m.id === model.id);
+          if (index !== -1) {
+            this.models[index] = model;
+          }
+        }
+        return imported;
+      } catch (e) {
+        this.error = String(e);
+        console.error('Failed to import model previews:', e);
+        return [];
+      }
+    },
+
+    async getModelThumbnail(modelId: string, size: ThumbnailSize = 256) {
+      try {
+        return await invoke<string | null>('get_model_thumbnail', { modelId, size });
+      } catch (e) {
+        console.error('Failed to load model thumbnail:', e);
+        return null;
+      }
+    },
+
+    async fetchLibraryStats() {
+      this.error = null;
+      try {
+        return await invoke<LibraryStats>('get_library_stats');
+      } catch (e) {
+        this.error = String(e);
+        console.error('Failed to fetch library stats:', e);
+        return null;
+      }
+    },
+
+    async fetchFamilies() {
+      this.error = null;
+      try {
+        this.families = await invoke<ModelFamily[]>('get_model_families');
+      } catch (e) {
+        this.error = String(e);
+        console.error('Failed to fetch model families:', e);
+      }
+    },
+
+    async saveFamily(family: ModelFamily) {
+      this.error = null;
+      try {
+        const command = family.id ? 'update_model_family' : 'create_model_family';
+        const saved = await invoke<ModelFamily>(command, { family });
+        const index = this.families.findIndex(f => f.id === saved.id);
+        if (index !== -1) {
+          this.families[index] = saved;
+        } else {
+          this.families.push(saved);
+        }
+        return saved;
+      } catch (e) {
+        this.error = String(e);
+        console.error('Failed to save model family:', e);
+        return null;
+      }
+    },
+
+    async deleteFamily(id: string) {
+      this.error = null;
+      try {
+        await invoke('delete_model_family', { id });
+        this.families = this.families.filter(f => f.id !== id);
+        return true;
+      } catch (e) {
+        this.error = String(e);
+        console.error('Failed to delete model family:', e);
+        return false;
+      }
+    },
+
+    async previewFamilyUpgrade(familyId: string, targetModelId?: string) {
+      this.error = null;
+      try {
+        return await invoke<PresetUpgrade[]>('preview_family_upgrade', { familyId, targetModelId });
+      } catch (e) {
+        this.error = String(e);
+        console.error('Failed to preview family upgrade:', e);
+        return [];
+      }
+    },
+
+    async upgradeFamilyPresets(familyId: string, targetModelId?: string, presetIds?: string[]) {
+      this.error = null;
+      try {
+        const touched = await invoke<PresetSummary[]>('upgrade_family_presets', { familyId, targetModelId, presetIds });
+        if (touched.length > 0) {
+          await usePresetStore().fetchPresets();
+        }
+        return touched;
+      } catch (e) {
+        this.error = String(e);
+        console.error('Failed to upgrade presets:', e);
+        return null;
+      }
+    },
+
     setFilter(filter: Partial<ModelFilterOptions>) {
       this.filter = { ...this.filter, ...filter };
     },
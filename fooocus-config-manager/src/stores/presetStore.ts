@@ -1,8 +1,9 @@
 import { defineStore } from 'pinia';
 import { invoke } from '@tauri-apps/api/core';
-import type { PresetConfig, Tag, FilterOptions } from '../types';
+import type { PresetConfig, Tag, FilterOptions, ModelSlot, RelinkReport, LintWarning, PresetImage, ThumbnailSize, ResourceIssue, PresetRevision, PresetChange, PresetMerge, MergeSide, PresetSummary, PresetEdit, BulkUpdateSummary, SweepAxis, SweepTarget, PresetSweep, Outcome, PresetOutcome, OutcomeSummary, PresetRanking } from '../types';
 import { createEmptyPresetConfig } from '../types';
 import { useModelStore } from './modelStore';
+import { useCollectionStore } from './collectionStore';
 
 interface PresetState {
   presets: PresetConfig[];
@@ -23,6 +24,7 @@ export const usePresetStore = defineStore('preset', {
       tags: [],
       isFavorite: null,
       baseModel: '',
+      collectionId: null,
       sortBy: 'updatedAt',
       sortOrder: 'desc',
     },
@@ -67,6 +69,14 @@ export const usePresetStore = defineStore('preset', {
         });
       }
 
+      // Collections keep their own manual order.
+      if (state.filter.collectionId) {
+        const presetIds = useCollectionStore().getCollectionById(state.filter.collectionId)?.presetIds ?? [];
+        return result
+          .filter(p => presetIds.includes(p.id))
+          .sort((a, b) => presetIds.indexOf(a.id) - presetIds.indexOf(b.id));
+      }
+
       result.sort((a, b) => {
         let comparison = 0;
         switch (state.filter.sortBy) {
@@ -82,6 +92,9 @@ export const usePresetStore = defineStore('preset', {
           case 'useCount':
             comparison = a.useCount - b.useCount;
             break;
+          case 'rating':
+            comparison = (a.rating ?? 0) - (b.rating ?? 0);
+            break;
         }
         return state.filter.sortOrder === 'desc' ? -comparison : comparison;
       });
@@ -163,6 +176,28 @@ export const usePresetStore = defineStore('preset', {
       }
     },
 
+    async duplicatePreset(id: string, newName: string) {
+      this.error = null;
+      try {
+        const duplicated = await invoke<PresetConfig>('duplicate_preset', { id, newName });
+        this.presets.unshift(duplicated);
+        return duplicated;
+      } catch (e) {
+        this.error = String(e);
+        console.error('Failed to duplicate preset:', e);
+        return null;
+      }
+    },
+
+    async fetchPresetForks(id: string) {
+      try {
+        return await invoke<PresetSummary[]>('get_preset_forks', { id });
+      } catch (e) {
+        console.error('Failed to fetch preset forks:', e);
+        return [];
+      }
+    },
+
     async updatePreset(preset: PresetConfig) {
       this.isLoading = true;
       this.error = null;
@@ -172,6 +207,10 @@ export const usePresetStore = defineStore('preset', {
         if (index !== -1) {
           this.presets[index] = updated;
         }
+        if (this.presets.some(p => p.parentId === updated.id)) {
+          // Children were updated along with their parent.
+          await this.fetchPresets();
+        }
         if (this.currentPreset?.id === preset.id) {
           this.currentPreset = updated;
         }
@@ -189,14 +228,19 @@ export const usePresetStore = defineStore('preset', {
       this.isLoading = true;
       this.error = null;
       try {
-        await invoke('delete_preset', { id });
-        this.presets = this.presets.filter(p => p.id !== id);
+        // Presets that inherited from it now stand on their own.
+        const detached = await invoke<PresetConfig[]>('delete_preset', { id });
+        this.presets = this.presets
+          .filter(p => p.id !== id)
+          .map(p => detached.find(d => d.id === p.id) ?? p);
         if (this.currentPreset?.id === id) {
           this.currentPreset = null;
         }
+        return true;
       } catch (e) {
         this.error = String(e);
         console.error('Failed to delete preset:', e);
+        return false;
       } finally {
         this.isLoading = false;
       }
@@ -245,6 +289,320 @@ export const usePresetStore = defineStore('preset', {
       }
     },
 
+    async relinkPresetModels() {
+      this.error = null;
+      try {
+        const report = await invoke<RelinkReport>('relink_preset_models');
+        if (report.updatedPresets > 0) {
+          await this.fetchPresets();
+        }
+        return report;
+      } catch (e) {
+        this.error = String(e);
+        console.error('Failed to relink preset models:', e);
+        return null;
+      }
+    },
+
+    async linkPresetModel(presetId: string, slot: ModelSlot, modelId: string) {
+      this.error = null;
+      try {
+        const updated = await invoke<PresetConfig>('link_preset_model', { presetId, slot, modelId });
+        const index = this.presets.findIndex(p => p.id === presetId);
+        if (index !== -1) {
+          this.presets[index] = updated;
+        }
+        return updated;
+      } catch (e) {
+        this.error = String(e);
+        console.error('Failed to link preset model:', e);
+        return null;
+      }
+    },
+
+    async lintPreset(preset: PresetConfig): Promise<LintWarning[]> {
+      try {
+        return await invoke<LintWarning[]>('lint_preset', { preset });
+      } catch (e) {
+        console.error('Failed to lint preset:', e);
+        return [];
+      }
+    },
+
+    async bulkUpdatePresets(ids: string[], patch: PresetEdit[], dryRun = false) {
+      this.error = null;
+      try {
+        const summaries = await invoke<BulkUpdateSummary[]>('bulk_update_presets', { ids, patch, dryRun });
+        if (!dryRun) {
+          await this.fetchPresets();
+        }
+        return summaries;
+      } catch (e) {
+        this.error = String(e);
+        console.error('Failed to bulk update presets:', e);
+        return null;
+      }
+    },
+
+    async setPresetRating(id: string, rating: number | null) {
+      this.error = null;
+      try {
+        await invoke('set_preset_rating', { id, rating });
+        const preset = this.presets.find(p => p.id === id);
+        if (preset) {
+          preset.rating = rating;
+        }
+        return true;
+      } catch (e) {
+        this.error = String(e);
+        console.error('Failed to set preset rating:', e);
+        return false;
+      }
+    },
+
+    async setPresetNotes(id: string, notes: string) {
+      this.error = null;
+      try {
+        await invoke('set_preset_notes', { id, notes });
+        const preset = this.presets.find(p => p.id === id);
+        if (preset) {
+          preset.notes = notes;
+        }
+        return true;
+      } catch (e) {
+        this.error = String(e);
+        console.error('Failed to set preset notes:', e);
+        return false;
+      }
+    },
+
+    async recordPresetOutcome(presetId: string, outcome: Outcome, comment?: string, usedAt?: string) {
+      this.error = null;
+      try {
+        return await invoke<PresetOutcome>('record_preset_outcome', { presetId, outcome, comment, usedAt });
+      } catch (e) {
+        this.error = String(e);
+        console.error('Failed to record preset outcome:', e);
+        return null;
+      }
+    },
+
+    async fetchPresetOutcomes(presetId: string) {
+      try {
+        return await invoke<PresetOutcome[]>('get_preset_outcomes', { presetId });
+      } catch (e) {
+        this.error = String(e);
+        console.error('Failed to fetch preset outcomes:', e);
+        return [];
+      }
+    },
+
+    async deletePresetOutcome(id: string) {
+      this.error = null;
+      try {
+        await invoke('delete_preset_outcome', { id });
+        return true;
+      } catch (e) {
+        this.error = String(e);
+        console.error('Failed to delete preset outcome:', e);
+        return false;
+      }
+    },
+
+    async fetchOutcomeSummaries() {
+      try {
+        return await invoke<OutcomeSummary[]>('get_outcome_summaries');
+      } catch (e) {
+        this.error = String(e);
+        console.error('Failed to fetch outcome summaries:', e);
+        return [];
+      }
+    },
+
+    async fetchTopRatedPresets(modelId?: string, limit?: number) {
+      try {
+        return await invoke<PresetRanking[]>('get_top_rated_presets', { modelId, limit });
+      } catch (e) {
+        this.error = String(e);
+        console.error('Failed to fetch top rated presets:', e);
+        return [];
+      }
+    },
+
+    async generatePresetSweep(presetId: string, axes: SweepAxis[], save?: SweepTarget) {
+      this.error = null;
+      try {
+        const sweep = await invoke<PresetSweep>('generate_preset_sweep', { presetId, axes, save });
+        if (save) {
+          await this.fetchPresets();
+          await useCollectionStore().fetchCollections();
+        }
+        return sweep;
+      } catch (e) {
+        this.error = String(e);
+        console.error('Failed to generate preset sweep:', e);
+        return null;
+      }
+    },
+
+    async fetchPresetRevisions(presetId: string) {
+      try {
+        return await invoke<PresetRevision[]>('get_preset_revisions', { presetId });
+      } catch (e) {
+        this.error = String(e);
+        console.error('Failed to fetch preset revisions:', e);
+        return [];
+      }
+    },
+
+    async diffPresetRevisions(fromRevisionId: string, toRevisionId: string) {
+      this.error = null;
+      try {
+        return await invoke<PresetChange[]>('diff_preset_revisions', { fromRevisionId, toRevisionId });
+      } catch (e) {
+        this.error = String(e);
+        console.error('Failed to diff preset revisions:', e);
+        return [];
+      }
+    },
+
+    async diffPresets(beforeId: string, afterId: string) {
+      this.error = null;
+      try {
+        return await invoke<PresetChange[]>('diff_presets', { beforeId, afterId });
+      } catch (e) {
+        this.error = String(e);
+        console.error('Failed to diff presets:', e);
+        return [];
+      }
+    },
+
+    async mergePresets(
+      oursId: string,
+      theirsId: string,
+      base: { baseRevisionId?: string; basePresetId?: string } = {},
+      resolutions: Record<string, MergeSide> = {},
+    ) {
+      this.error = null;
+      try {
+        return await invoke<PresetMerge>('merge_presets', { oursId, theirsId, ...base, resolutions });
+      } catch (e) {
+        this.error = String(e);
+        console.error('Failed to merge presets:', e);
+        return null;
+      }
+    },
+
+    async restorePresetRevision(revisionId: string) {
+      this.error = null;
+      try {
+        const restored = await invoke<PresetConfig>('restore_preset_revision', { revisionId });
+        const index = this.presets.findIndex(p => p.id === restored.id);
+        if (index !== -1) {
+          this.presets[index] = restored;
+        }
+        if (this.currentPreset?.id === restored.id) {
+          this.currentPreset = restored;
+        }
+        return restored;
+      } catch (e) {
+        this.error = String(e);
+        console.error('Failed to restore preset revision:', e);
+        return null;
+      }
+    },
+
+    async fetchPresetImages(presetId: string) {
+      try {
+        return await invoke<PresetImage[]>('get_preset_images', { presetId });
+      } catch (e) {
+        this.error = String(e);
+        console.error('Failed to fetch preset images:', e);
+        return [];
+      }
+    },
+
+    async addPresetImages(presetId: string, paths: string[], copy: boolean) {
+      this.error = null;
+      try {
+        return await invoke<PresetImage[]>('add_preset_images', { presetId, paths, copy });
+      } catch (e) {
+        this.error = String(e);
+        console.error('Failed to add preset images:', e);
+        return [];
+      }
+    },
+
+    async updatePresetImage(imageId: string, notes: string, rating: number | null) {
+      this.error = null;
+      try {
+        return await invoke<PresetImage>('update_preset_image', { imageId, notes, rating });
+      } catch (e) {
+        this.error = String(e);
+        console.error('Failed to update preset image:', e);
+        return null;
+      }
+    },
+
+    async setPresetCover(presetId: string, imageId: string) {
+      this.error = null;
+      try {
+        await invoke('set_preset_cover', { presetId, imageId });
+        return true;
+      } catch (e) {
+        this.error = String(e);
+        console.error('Failed to set preset cover:', e);
+        return false;
+      }
+    },
+
+    async removePresetImage(imageId: string) {
+      this.error = null;
+      try {
+        await invoke('remove_preset_image', { imageId });
+        return true;
+      } catch (e) {
+        this.error = String(e);
+        console.error('Failed to remove preset image:', e);
+        return false;
+      }
+    },
+
+    async getPresetImageThumbnail(imageId: string, size: ThumbnailSize = 256) {
+      try {
+        return await invoke<string | null>('get_preset_image_thumbnail', { imageId, size });
+      } catch (e) {
+        console.error('Failed to load preset image thumbnail:', e);
+        return null;
+      }
+    },
+
+    async getPresetCoverThumbnail(presetId: string, size: ThumbnailSize = 256) {
+      try {
+        return await invoke<string | null>('get_preset_cover_thumbnail', { presetId, size });
+      } catch (e) {
+        console.error('Failed to load preset cover:', e);
+        return null;
+      }
+    },
+
+    async fetchResourceIssues() {
+      try {
+        return await invoke<ResourceIssue[]>('get_resource_issues');
+      } catch (e) {
+        console.error('Failed to fetch resource issues:', e);
+        return [];
+      }
+    },
+
+    async clearResourceIssues() {
+      try {
+        await invoke('clear_resource_issues');
+      } catch (e) {
+        console.error('Failed to clear resource issues:', e);
+      }
+    },
+
     async fetchTags() {
       this.error = null;
       try {
@@ -0,0 +1,89 @@
+import { defineStore } from 'pinia';
+import { invoke } from '@tauri-apps/api/core';
+import type { TrashItem, TrashKind } from '../types';
+import { useModelStore } from './modelStore';
+import { usePresetStore } from './presetStore';
+
+interface TrashState {
+  items: TrashItem[];
+  retentionDays: number;
+  purgeError: string | null;
+  isLoading: boolean;
+  error: string | null;
+}
+
+export const useTrashStore = defineStore('trash', {
+  state: (): TrashState => ({
+    items: [],
+    retentionDays: 30,
+    purgeError: null,
+    isLoading: false,
+    error: null,
+  }),
+
+  actions: {
+    async fetchTrash() {
+      this.isLoading = true;
+      this.error = null;
+      try {
+        this.items = await invoke<TrashItem[]>('get_trash');
+        this.retentionDays = await invoke<number>('get_trash_retention_days');
+        this.purgeError = await invoke<string | null>('get_trash_purge_error');
+      } catch (e) {
+        this.error = String(e);
+        console.error('Failed to fetch trash:', e);
+      } finally {
+        this.isLoading = false;
+      }
+    },
+
+    async restore(kind: TrashKind, id: string) {
+      this.error = null;
+      try {
+        await invoke('restore_from_trash', { kind, id });
+        this.items = this.items.filter(i => !(i.kind === kind && i.id === id));
+        const presetStore = usePresetStore();
+        if (kind === 'model') {
+          await useModelStore().fetchModels();
+        }
+        if (kind === 'tag') {
+          await presetStore.fetchTags();
+        } else {
+          await presetStore.fetchPresets();
+        }
+        return true;
+      } catch (e) {
+        this.error = String(e);
+        console.error('Failed to restore from trash:', e);
+        return false;
+      }
+    },
+
+    async purge(olderThanDays?: number) {
+      this.error = null;
+      try {
+        const purged = await invoke<TrashItem[]>('purge_trash', { olderThanDays });
+        this.items = this.items.filter(i => !purged.some(p => p.kind === i.kind && p.id === i.id));
+        this.purgeError = null;
+        return purged;
+      } catch (e) {
+        this.error = String(e);
+        console.error('Failed to purge trash:', e);
+        return null;
+      }
+    },
+
+    async saveRetentionDays(days: number) {
+      this.error = null;
+      try {
+        await invoke('set_trash_retention_days', { days });
+        this.retentionDays = days;
+        return true;
+      } catch (e) {
+        this.error = String(e);
+        console.error('Failed to save trash retention:', e);
+        return false;
+      }
+    },
+  },
+});
@@ -12,6 +12,8 @@ export interface ModelConfig {
   refinerModelId?: string;
   refinerSwitch: number;
   loras: LoRA[];
+  vae: string;
+  vaeModelId?: string;
 }
 
 export interface ModelUsageInfo {
@@ -20,6 +22,52 @@ export interface ModelUsageInfo {
   presetNames: string[];
 }
 
+export type ModelSlot =
+  | { kind: 'base' }
+  | { kind: 'refiner' }
+  | { kind: 'lora'; index: number }
+  | { kind: 'vae' };
+
+export type MatchStrategy = 'exact' | 'caseInsensitive' | 'extensionInsensitive';
+
+export interface ModelCandidate {
+  id: string;
+  name: string;
+  fileName: string;
+}
+
+export interface LinkedReference {
+  presetId: string;
+  presetName: string;
+  slot: ModelSlot;
+  modelName: string;
+  modelId: string;
+  strategy: MatchStrategy;
+}
+
+export interface AmbiguousReference {
+  presetId: string;
+  presetName: string;
+  slot: ModelSlot;
+  modelName: string;
+  strategy: MatchStrategy;
+  candidates: ModelCandidate[];
+}
+
+export interface UnresolvedReference {
+  presetId: string;
+  presetName: string;
+  slot: ModelSlot;
+  modelName: string;
+}
+
+export interface RelinkReport {
+  linked: LinkedReference[];
+  ambiguous: AmbiguousReference[];
+  unresolved: UnresolvedReference[];
+  updatedPresets: number;
+}
+
 export type PerformanceMode = 'Speed' | 'Quality' | 'Lightning';
 
 export interface SamplingConfig {
@@ -42,10 +90,15 @@ export interface ImageConfig {
   imageCount: number;
 }
 
+export interface ResourceEntry {
+  url: string;
+  sha256?: string;
+}
+
 export interface ResourceDownloads {
-  checkpointDownloads?: Record<string, string>;
-  loraDownloads?: Record<string, string>;
-  embeddingDownloads?: Record<string, string>;
+  checkpointDownloads?: Record<string, ResourceEntry>;
+  loraDownloads?: Record<string, ResourceEntry>;
+  embeddingDownloads?: Record<string, ResourceEntry>;
 }
 
 export interface PresetConfig {
@@ -62,6 +115,12 @@ export interface PresetConfig {
   prompt: PromptConfig;
   image: ImageConfig;
   resources?: ResourceDownloads;
+  parentId?: string;
+  inheritedFields?: string[];
+  sourcePresetId?: string;
+  rating?: number | null;
+  // Markdown.
+  notes?: string;
 }
 
 export interface Tag {
@@ -71,7 +130,16 @@ export interface Tag {
   count: number;
 }
 
-export type ModelType = 'Checkpoint' | 'LoRA' | 'Refiner' | 'Embedding';
+export type ModelType =
+  | 'Checkpoint'
+  | 'LoRA'
+  | 'Refiner'
+  | 'Embedding'
+  | 'VAE'
+  | 'Upscaler'
+  | 'ControlNet'
+  | 'IP-Adapter'
+  | 'Inpaint';
 
 export interface ModelInfo {
   id: string;
@@ -84,6 +152,357 @@ export interface ModelInfo {
   tags: string[];
   createdAt: string;
   updatedAt: string;
+  triggerWords: string[];
+  recommendedWeightMin?: number | null;
+  recommendedWeightMax?: number | null;
+  recommendedBaseModel?: string | null;
+  sourceUrl?: string;
+  previewImage?: string | null;
+}
+
+export type ThumbnailSize = 128 | 256 | 512;
+
+export interface FooocusPaths {
+  checkpointsDir: string;
+  lorasDir: string;
+  embeddingsDir: string;
+}
+
+export type DownloadKind = 'checkpoint' | 'lora' | 'embedding';
+
+export interface DownloadRequest {
+  kind: DownloadKind;
+  fileName: string;
+  url: string;
+  sha256?: string;
+}
+
+export type DownloadStatus =
+  | { kind: 'queued' }
+  | { kind: 'downloading'; downloaded: number; total: number | null }
+  | { kind: 'verifying' }
+  | { kind: 'completed' }
+  | { kind: 'skipped' }
+  | { kind: 'failed'; message: string };
+
+export interface DownloadProgress {
+  fileName: string;
+  kind: DownloadKind;
+  status: DownloadStatus;
+}
+
+export interface ResourceIssue {
+  presetId: string;
+  presetName: string;
+  kind: DownloadKind | null;
+  fileName: string;
+  value: unknown;
+  reason: string;
+}
+
+export interface DownloadResult {
+  request: DownloadRequest;
+  status: DownloadStatus;
+  path?: string;
+}
+
+export interface DownloadReport {
+  results: DownloadResult[];
+  registered: ModelInfo[];
+}
+
+export interface ModelStats {
+  modelId: string;
+  name: string;
+  fileName: string;
+  modelType: string;
+  architecture: string;
+  fileSize: number | null;
+  presetCount: number;
+  totalUseCount: number;
+}
+
+export interface SizeBucket {
+  key: string;
+  modelCount: number;
+  totalSize: number;
+}
+
+export interface LibraryStats {
+  totalSize: number;
+  models: ModelStats[];
+  byType: SizeBucket[];
+  byArchitecture: SizeBucket[];
+  orphans: ModelStats[];
+  missingFiles: ModelStats[];
+}
+
+export interface PresetRevision {
+  id: string;
+  presetId: string;
+  revision: number;
+  createdAt: string;
+  changedFields: string[];
+  restoredFrom?: number;
+}
+
+export interface PresetFieldChange {
+  path: string;
+  before: unknown;
+  after: unknown;
+}
+
+export interface WordSpan {
+  op: 'equal' | 'insert' | 'delete';
+  text: string;
+}
+
+export type PresetChange =
+  | ({ kind: 'field' } & PresetFieldChange)
+  | { kind: 'loraAdded'; lora: LoRA }
+  | { kind: 'loraRemoved'; lora: LoRA }
+  | { kind: 'loraReweighted'; lora: LoRA; before: number; after: number }
+  | { kind: 'setChanged'; path: string; added: string[]; removed: string[] }
+  | { kind: 'promptChanged'; negative: boolean; spans: WordSpan[] };
+
+export type PresetEdit =
+  | { op: 'setField'; path: string; value: unknown }
+  | { op: 'scaleField'; path: string; factor: number }
+  | { op: 'addTag'; tag: string }
+  | { op: 'removeTag'; tag: string }
+  | { op: 'replaceLora'; from: string; to: string; weight?: number }
+  | { op: 'removeLora'; modelName: string }
+  | { op: 'addLora'; lora: LoRA }
+  | { op: 'appendPrompt'; negative: boolean; text: string };
+
+export interface BulkUpdateSummary {
+  presetId: string;
+  presetName: string;
+  changes: PresetChange[];
+}
+
+export interface Collection {
+  id: string;
+  name: string;
+  description: string;
+  parentId?: string | null;
+  position: number;
+  presetIds: string[];
+  createdAt: string;
+  updatedAt: string;
+}
+
+export interface CollectionExport {
+  name: string;
+  description: string;
+  presets: PresetConfig[];
+  children: CollectionExport[];
+}
+
+export type SweepValues =
+  | { kind: 'list'; values: unknown[] }
+  | { kind: 'range'; start: number; end: number; step: number };
+
+export interface SweepAxis {
+  path: string;
+  values: SweepValues;
+}
+
+export interface SweepSetting {
+  path: string;
+  value: unknown;
+}
+
+export interface SweepVariant {
+  preset: PresetConfig;
+  settings: SweepSetting[];
+}
+
+export type SweepTarget =
+  | { kind: 'library' }
+  | { kind: 'collection'; collectionId: string }
+  | { kind: 'newCollection'; name: string; parentId?: string | null };
+
+export interface PresetSweep {
+  variants: SweepVariant[];
+  collectionId: string | null;
+}
+
+export type MergeSide = 'base' | 'ours' | 'theirs';
+
+export interface MergeConflict {
+  path: string;
+  base: unknown;
+  ours: unknown;
+  theirs: unknown;
+  resolution: MergeSide;
+}
+
+export interface PresetMerge {
+  merged: PresetConfig;
+  conflicts: MergeConflict[];
+}
+
+export type Outcome = 'kept' | 'discarded';
+
+export interface PresetOutcome {
+  id: string;
+  presetId: string;
+  outcome: Outcome;
+  comment: string;
+  usedAt: string;
+}
+
+export interface OutcomeSummary {
+  presetId: string;
+  kept: number;
+  discarded: number;
+  lastUsedAt: string | null;
+}
+
+export interface PresetRanking {
+  preset: PresetConfig;
+  kept: number;
+  discarded: number;
+}
+
+export type TrashKind = 'preset' | 'model' | 'tag';
+
+export interface TrashItem {
+  kind: TrashKind;
+  id: string;
+  name: string;
+  deletedAt: string;
+}
+
+export interface FamilyVersion {
+  modelId: string;
+  label: string;
+}
+
+export interface ModelFamily {
+  id: string;
+  name: string;
+  description: string;
+  versions: FamilyVersion[];
+  createdAt: string;
+  updatedAt: string;
+}
+
+export interface ReferenceUpgrade {
+  slot: ModelSlot;
+  fromModelId: string;
+  fromFileName: string;
+  fromVersion: string;
+  toModelId: string;
+  toFileName: string;
+  toVersion: string;
+}
+
+export interface PresetUpgrade {
+  presetId: string;
+  presetName: string;
+  changes: ReferenceUpgrade[];
+}
+
+export interface PresetImage {
+  id: string;
+  presetId: string;
+  path: string;
+  isCopy: boolean;
+  isCover: boolean;
+  notes: string;
+  rating: number | null;
+  sortOrder: number;
+  createdAt: string;
+}
+
+export interface FieldChange {
+  field: string;
+  current: unknown;
+  incoming: unknown;
+  overwrites: boolean;
+}
+
+export interface SidecarImportPreview {
+  modelId: string;
+  modelName: string;
+  files: string[];
+  changes: FieldChange[];
+}
+
+export type LintWarning =
+  | {
+      kind: 'missingTriggerWords';
+      loraIndex: number;
+      modelId: string;
+      modelName: string;
+      triggerWords: string[];
+    }
+  | {
+      kind: 'weightOutOfRange';
+      loraIndex: number;
+      modelId: string;
+      modelName: string;
+      weight: number;
+      min?: number;
+      max?: number;
+    }
+  | {
+      kind: 'missingEmbedding';
+      name: string;
+      negative: boolean;
+    }
+  | {
+      kind: 'incompatibleVae';
+      modelId: string;
+      modelName: string;
+      vaeBaseModel: string;
+      checkpointBaseModel: string;
+    };
+
+export interface PresetSummary {
+  id: string;
+  name: string;
+}
+
+export interface ModelUpdateResult {
+  model: ModelInfo;
+  touchedPresets: PresetSummary[];
+}
+
+export type DeleteStrategy =
+  | { kind: 'refuse' }
+  | { kind: 'detach' }
+  | { kind: 'replace'; replacementId: string }
+  | { kind: 'removeLora' };
+
+export interface ModelDeletionResult {
+  undoId: string;
+  touchedPresets: PresetSummary[];
+}
+
+export interface DuplicateModelEntry {
+  model: ModelInfo;
+  filePath: string;
+  presets: PresetSummary[];
+}
+
+export interface DuplicateGroup {
+  hash: string;
+  size: number;
+  models: DuplicateModelEntry[];
+  unregisteredFiles: string[];
+}
+
+export interface DuplicateReport {
+  groups: DuplicateGroup[];
+  missingFiles: ModelCandidate[];
+}
+
+export interface ModelMergeResult {
+  removedModelIds: string[];
+  touchedPresets: PresetSummary[];
 }
 
 export interface ModelFilterOptions {
@@ -99,7 +518,8 @@ export interface FilterOptions {
   tags: string[];
   isFavorite: boolean | null;
   baseModel: string;
-  sortBy: 'name' | 'updatedAt' | 'createdAt' | 'useCount';
+  collectionId: string | null;
+  sortBy: 'name' | 'updatedAt' | 'createdAt' | 'useCount' | 'rating';
   sortOrder: 'asc' | 'desc';
 }
 
@@ -156,6 +576,36 @@ export const DEFAULT_ASPECT_RATIOS = [
   '1728*576',
 ] as const;
 
+export interface PresetSettingField {
+  label: string;
+  path: string;
+  numeric?: boolean;
+  options?: { label: string; value: string }[];
+}
+
+const toOptions = (values: readonly string[]) => values.map(v => ({ label: v, value: v }));
+
+// Settings that bulk edits and parameter sweeps can target, by field path.
+export const PRESET_SETTING_FIELDS: PresetSettingField[] = [
+  { label: '采样器', path: 'sampling.sampler', options: toOptions(DEFAULT_SAMPLERS) },
+  { label: '调度器', path: 'sampling.scheduler', options: toOptions(DEFAULT_SCHEDULERS) },
+  {
+    label: '性能模式',
+    path: 'sampling.performance',
+    options: [
+      { label: '速度优先', value: 'Speed' },
+      { label: '质量优先', value: 'Quality' },
+      { label: 'Lightning', value: 'Lightning' },
+    ],
+  },
+  { label: '步数', path: 'sampling.steps', numeric: true },
+  { label: 'CFG Scale', path: 'sampling.cfgScale', numeric: true },
+  { label: '采样锐度', path: 'sampling.sampleSharpness', numeric: true },
+  { label: '精修切换点', path: 'model.refinerSwitch', numeric: true },
+  { label: '宽高比', path: 'image.aspectRatio', options: toOptions(DEFAULT_ASPECT_RATIOS) },
+  { label: '图像数量', path: 'image.imageCount', numeric: true },
+];
+
 export function createEmptyPresetConfig(): PresetConfig {
   const now = new Date().toISOString();
   return {
@@ -165,6 +615,7 @@ export function createEmptyPresetConfig(): PresetConfig {
     tags: [],
     isFavorite: false,
     useCount: 0,
+    notes: '',
     createdAt: now,
     updatedAt: now,
     model: {
@@ -172,6 +623,7 @@ export function createEmptyPresetConfig(): PresetConfig {
       refinerModel: '',
       refinerSwitch: 0.5,
       loras: [],
+      vae: '',
     },
     sampling: {
       cfgScale: 7.0,
@@ -206,10 +658,21 @@ export function createEmptyModelInfo(): ModelInfo {
     tags: [],
     createdAt: now,
     updatedAt: now,
+    triggerWords: [],
   };
 }
 
-export const MODEL_TYPES: ModelType[] = ['Checkpoint', 'LoRA', 'Refiner', 'Embedding'];
+export const MODEL_TYPES: ModelType[] = [
+  'Checkpoint',
+  'LoRA',
+  'Refiner',
+  'Embedding',
+  'VAE',
+  'Upscaler',
+  'ControlNet',
+  'IP-Adapter',
+  'Inpaint',
+];
 
 export const DEFAULT_SCOPE_TAGS = [
   '人像',
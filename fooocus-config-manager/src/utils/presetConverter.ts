@@ -1,10 +1,24 @@
-import type { PresetConfig, LoRA, PerformanceMode } from '../types';
+import type { PresetConfig, LoRA, PerformanceMode, ResourceEntry, CollectionExport } from '../types';
+
+// Fooocus' value for "use the VAE baked into the checkpoint".
+const DEFAULT_VAE = 'Default (model)';
+
+function toFooocusDownloads(map?: Record<string, ResourceEntry>): Record<string, string> | undefined {
+  if (!map) return undefined;
+  return Object.fromEntries(Object.entries(map).map(([fileName, entry]) => [fileName, entry.url]));
+}
+
+function fromFooocusDownloads(map?: Record<string, string>): Record<string, ResourceEntry> | undefined {
+  if (!map) return undefined;
+  return Object.fromEntries(Object.entries(map).map(([fileName, url]) => [fileName, { url }]));
+}
 
 interface FooocusPreset {
   default_model: string;
   default_refiner_model: string;
   default_refiner_switch: number;
   default_loras: Array<[string, string, number]>;
+  default_vae?: string;
   default_cfg_scale: number;
   default_sample_sharpness: number;
   default_sampler: string;
@@ -37,6 +51,7 @@ export function exportToFooocusPreset(preset: PresetConfig): FooocusPreset {
       lora.modelName,
       lora.weight,
     ] as [string, string, number]),
+    default_vae: preset.model.vae || DEFAULT_VAE,
     default_cfg_scale: preset.sampling.cfgScale,
     default_sample_sharpness: preset.sampling.sampleSharpness,
     default_sampler: preset.sampling.sampler,
@@ -54,9 +69,9 @@ export function exportToFooocusPreset(preset: PresetConfig): FooocusPreset {
     default_cfg_tsnr: 7,
     default_negative_prompt: preset.prompt.negative,
     default_positive_prompt: preset.prompt.positive,
-    checkpoint_downloads: preset.resources?.checkpointDownloads,
-    lora_downloads: preset.resources?.loraDownloads,
-    embedding_downloads: preset.resources?.embeddingDownloads,
+    checkpoint_downloads: toFooocusDownloads(preset.resources?.checkpointDownloads),
+    lora_downloads: toFooocusDownloads(preset.resources?.loraDownloads),
+    embedding_downloads: toFooocusDownloads(preset.resources?.embeddingDownloads),
   };
 }
 
@@ -86,6 +101,7 @@ export function importFromFooocusPreset(
       refinerModel: fooocusPreset.default_refiner_model || '',
       refinerSwitch: fooocusPreset.default_refiner_switch || 0.5,
       loras,
+      vae: fooocusPreset.default_vae && fooocusPreset.default_vae !== DEFAULT_VAE ? fooocusPreset.default_vae : '',
     },
     sampling: {
       cfgScale: fooocusPreset.default_cfg_scale || 7.0,
@@ -105,9 +121,9 @@ export function importFromFooocusPreset(
       imageCount: 4,
     },
     resources: {
-      checkpointDownloads: fooocusPreset.checkpoint_downloads,
-      loraDownloads: fooocusPreset.lora_downloads,
-      embeddingDownloads: fooocusPreset.embedding_downloads,
+      checkpointDownloads: fromFooocusDownloads(fooocusPreset.checkpoint_downloads),
+      loraDownloads: fromFooocusDownloads(fooocusPreset.lora_downloads),
+      embeddingDownloads: fromFooocusDownloads(fooocusPreset.embedding_downloads),
     },
   };
 }
@@ -127,6 +143,44 @@ export async function downloadPresetAsJson(preset: PresetConfig): Promise<void>
   URL.revokeObjectURL(url);
 }
 
+// Exports each preset as its own Fooocus preset file.
+export async function downloadPresetsAsJson(presets: PresetConfig[]): Promise<void> {
+  for (const preset of presets) {
+    await downloadPresetAsJson(preset);
+  }
+}
+
+interface FooocusCollection {
+  name: string;
+  description: string;
+  presets: Array<{ name: string; preset: FooocusPreset }>;
+  collections: FooocusCollection[];
+}
+
+function exportCollection(collection: CollectionExport): FooocusCollection {
+  return {
+    name: collection.name,
+    description: collection.description,
+    presets: collection.presets.map(preset => ({ name: preset.name, preset: exportToFooocusPreset(preset) })),
+    collections: collection.children.map(exportCollection),
+  };
+}
+
+// Exports a collection and its sub-collections as one file of Fooocus presets.
+export async function downloadCollectionAsJson(collection: CollectionExport): Promise<void> {
+  const json = JSON.stringify(exportCollection(collection), null, 2);
+  const blob = new Blob([json], { type: 'application/json' });
+  const url = URL.createObjectURL(blob);
+
+  const a = document.createElement('a');
+  a.href = url;
+  a.download = `${collection.name || 'collection'}.json`;
+  document.body.appendChild(a);
+  a.click();
+  document.body.removeChild(a);
+  URL.revokeObjectURL(url);
+}
+
 export function parseFooocusPresetJson(jsonString: string): PresetConfig {
   const fooocusPreset: FooocusPreset = JSON.parse(jsonString);
   return importFromFooocusPreset(fooocusPreset);